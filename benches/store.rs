@@ -0,0 +1,185 @@
+//! Throughput of the in-memory registry under concurrent registrations and reads,
+//! as with hundreds of sidecars re-registering while clients list the hubs.
+//! Every benchmark also runs against a registry behind one lock, as a baseline for the snapshots.
+//!
+//! cargo bench --bench store
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion, Throughput};
+use criterion::measurement::WallTime;
+use tokio::runtime::Runtime;
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+
+// the bench uses part of the store, and compiles its tests without their test functions
+#[allow(dead_code, unused_imports)]
+#[path = "../src/bin/store/mod.rs"]
+mod store;
+
+use store::backend::Store;
+use store::inmemory::InMemoryStore;
+use store::model::{GSFHealth, GSFInstance, InstanceType, RelayStatus};
+
+const NAMESPACE: &str = "default";
+const HUBS: usize = 500;
+const READERS: usize = 8;
+const READS_PER_READER: usize = 100;
+const REGISTRATIONS: usize = 1000;
+
+/// The registrations and listings of the Discovery Server, for both registries
+#[tonic::async_trait]
+trait Registry: Clone + Send + Sync + 'static {
+  async fn register(&self, hub: GSFInstance);
+  async fn list(&self) -> usize;
+}
+
+#[tonic::async_trait]
+impl Registry for InMemoryStore {
+  async fn register(&self, hub: GSFInstance) {
+    self.add_instance(NAMESPACE, hub, None).await.unwrap();
+  }
+
+  async fn list(&self) -> usize {
+    self.get_instances(NAMESPACE, Some(InstanceType::Hub)).await.unwrap().len()
+  }
+}
+
+/// The baseline: one lock around the hubs, which readers hold while copying them
+#[derive(Debug, Clone, Default)]
+struct LockedRegistry {
+  hubs: Arc<Mutex<HashMap<String, GSFInstance>>>,
+}
+
+#[tonic::async_trait]
+impl Registry for LockedRegistry {
+  async fn register(&self, hub: GSFInstance) {
+    self.hubs.lock().await.insert(hub.id.clone(), hub);
+  }
+
+  async fn list(&self) -> usize {
+    self.hubs.lock().await.values().cloned().collect::<Vec<_>>().len()
+  }
+}
+
+/// The hub at the given version, so every registration of a new version changes the registry
+fn hub(index: usize, version: usize) -> GSFInstance {
+  GSFInstance {
+    id: format!("hub-{}", index),
+    instance_type: InstanceType::Hub,
+    name: format!("Gitstafette Hub {}", index),
+    version: format!("0.1.{}", version),
+    host: format!("10.0.{}.{}", index / 256, index % 256),
+    port: "50051".to_string(),
+    repositories: "joostvdg/gitstafette,joostvdg/gitstafette-discovery".to_string(),
+    relay_host: "relay.example.com".to_string(),
+    relay_port: "443".to_string(),
+    health: GSFHealth::default(),
+    relay_status: RelayStatus::default(),
+    labels: BTreeMap::from([("env".to_string(), "prod".to_string())]),
+    resource_version: 0,
+  }
+}
+
+fn populated<R: Registry>(runtime: &Runtime, registry: R) -> R {
+  runtime.block_on(async {
+    for index in 0..HUBS {
+      registry.register(hub(index, 0)).await;
+    }
+  });
+  registry
+}
+
+/// Background load until the returned flag is set: registrations of new hub versions (writers),
+/// or listings of all hubs (readers)
+fn start_load<R: Registry>(runtime: &Runtime, registry: &R, writers: usize, readers: usize) -> Arc<AtomicBool> {
+  let stop = Arc::new(AtomicBool::new(false));
+  for writer in 0..writers {
+    let (registry, stop) = (registry.clone(), stop.clone());
+    runtime.spawn(async move {
+      let mut round = 1;
+      while !stop.load(Ordering::Relaxed) {
+        registry.register(hub((writer + round) % HUBS, round)).await;
+        round += 1;
+        tokio::task::yield_now().await;
+      }
+    });
+  }
+  for _ in 0..readers {
+    let (registry, stop) = (registry.clone(), stop.clone());
+    runtime.spawn(async move {
+      while !stop.load(Ordering::Relaxed) {
+        registry.list().await;
+        tokio::task::yield_now().await;
+      }
+    });
+  }
+  stop
+}
+
+fn bench_get_hubs<R: Registry>(group: &mut BenchmarkGroup<WallTime>, runtime: &Runtime, name: &str, registry: impl Fn() -> R) {
+  for writers in [0, 4] {
+    let registry = populated(runtime, registry());
+    let stop = start_load(runtime, &registry, writers, 0);
+    group.bench_with_input(BenchmarkId::new(format!("{}/concurrent_writers", name), writers), &registry, |b, registry| {
+      b.iter(|| {
+        runtime.block_on(async {
+          let mut readers = JoinSet::new();
+          for _ in 0..READERS {
+            let registry = registry.clone();
+            readers.spawn(async move {
+              for _ in 0..READS_PER_READER {
+                assert_eq!(registry.list().await, HUBS);
+              }
+            });
+          }
+          while readers.join_next().await.is_some() {}
+        })
+      })
+    });
+    stop.store(true, Ordering::Relaxed);
+  }
+}
+
+fn get_hubs(c: &mut Criterion) {
+  let runtime = Runtime::new().unwrap();
+  let mut group = c.benchmark_group("get_hubs");
+  group.throughput(Throughput::Elements((READERS * READS_PER_READER) as u64));
+  bench_get_hubs(&mut group, &runtime, "snapshot", InMemoryStore::new);
+  bench_get_hubs(&mut group, &runtime, "mutex", LockedRegistry::default);
+  group.finish();
+}
+
+fn bench_add_hub<R: Registry>(group: &mut BenchmarkGroup<WallTime>, runtime: &Runtime, name: &str, registry: impl Fn() -> R) {
+  for readers in [0, READERS] {
+    let registry = populated(runtime, registry());
+    let stop = start_load(runtime, &registry, 0, readers);
+    // every registration is a new version of a hub, so each one builds and swaps in a new snapshot
+    let mut version = 0;
+    group.bench_with_input(BenchmarkId::new(format!("{}/concurrent_readers", name), readers), &registry, |b, registry| {
+      b.iter(|| {
+        runtime.block_on(async {
+          for index in 0..REGISTRATIONS {
+            version += 1;
+            registry.register(hub(index % HUBS, version)).await;
+          }
+        })
+      })
+    });
+    stop.store(true, Ordering::Relaxed);
+  }
+}
+
+fn add_hub(c: &mut Criterion) {
+  let runtime = Runtime::new().unwrap();
+  let mut group = c.benchmark_group("add_hub");
+  group.throughput(Throughput::Elements(REGISTRATIONS as u64));
+  bench_add_hub(&mut group, &runtime, "snapshot", InMemoryStore::new);
+  bench_add_hub(&mut group, &runtime, "mutex", LockedRegistry::default);
+  group.finish();
+}
+
+criterion_group!(benches, get_hubs, add_hub);
+criterion_main!(benches);
\ No newline at end of file
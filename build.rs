@@ -11,17 +11,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // https://timvw.be/2022/04/28/notes-on-using-grpc-with-rust-and-tonic/
     let original_out_dir = PathBuf::from(env::var("OUT_DIR")?);
 
-    let out_dir = "./src/bin";
+    let out_dir = "./src/proto";
 
+    // labels are maps, ordered so they compare and print the same on every call
     tonic_build::configure()
         .out_dir(out_dir)
+        .btree_map(["."])
         .file_descriptor_set_path(original_out_dir.join("gitstafette_discovery.bin"))
-        .compile(&["./protos/gitstafette_discovery.proto"], &["proto"])?;
+        .compile(&["./protos/gitstafette_discovery.proto"], &["./protos"])?;
 
     tonic_build::configure()
         .out_dir(out_dir)
         .file_descriptor_set_path(original_out_dir.join("gitstafette_info.bin"))
-        .compile(&[ "./protos/gitstafette_info.proto"], &["proto"])?;
+        .compile(&[ "./protos/gitstafette_info.proto"], &["./protos"])?;
 
     vergen::EmitBuilder::builder()
         .git_sha(true)
@@ -0,0 +1,200 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use tokio::sync::broadcast;
+use tokio_stream::{Stream, StreamExt};
+use serde::Deserialize;
+use tokio_stream::wrappers::BroadcastStream;
+
+use crate::namespace::policy::{bearer_token, Access, NamespacePolicy};
+use crate::namespace::scope::{validate, NamespaceError};
+use crate::store::events::StoreEvent;
+use crate::store::backend::{Store, StoreError, StoreResult};
+
+const LAST_EVENT_ID_HEADER: &str = "Last-Event-ID";
+
+#[derive(Debug, Clone)]
+pub struct EventsState<S: Store> {
+  pub store: S,
+  pub policy: Arc<NamespacePolicy>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+  /// only the events of this namespace
+  namespace: Option<String>,
+}
+
+/// Streams the Store events as Server-Sent Events, with the Store revision as event id.
+/// A client that reconnects with `Last-Event-ID` receives the events it missed,
+/// or a `reset` event if those are no longer available and it should list everything again.
+/// Only the events of the namespaces the `Authorization: Bearer` token may read are sent,
+/// or of the one `?namespace=` namespace, which fails with 401 or 403 when the token may not read it.
+/// Fails with 503 when the Store is unavailable.
+pub async fn events_handler<S: Store>(State(state): State<EventsState<S>>, Query(query): Query<EventsQuery>, headers: HeaderMap) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, String)> {
+  let unavailable = |error: StoreError| (StatusCode::SERVICE_UNAVAILABLE, error.to_string());
+  let store = state.store;
+  let policy = state.policy;
+  let token = bearer_token(headers.get(header::AUTHORIZATION).and_then(|value| value.to_str().ok())).map(str::to_string);
+  if let Some(namespace) = &query.namespace {
+    validate(namespace)
+      .and_then(|_| policy.authorize(namespace, Access::Read, token.as_deref()))
+      .map_err(to_http_error)?;
+  }
+  let visible = move |event: &StoreEvent| match &query.namespace {
+    Some(namespace) => event.namespace == *namespace,
+    None => policy.authorize(&event.namespace, Access::Read, token.as_deref()).is_ok(),
+  };
+  let last_event_id = headers
+    .get(LAST_EVENT_ID_HEADER)
+    .and_then(|value| value.to_str().ok())
+    .and_then(|value| value.trim().parse::<u64>().ok());
+
+  // subscribe before reading the history, so no event falls in between
+  let live_events = store.subscribe();
+  let stream = outgoing_events(&store, live_events, last_event_id)
+    .await
+    .map_err(unavailable)?
+    .filter(move |outgoing| match outgoing {
+      Outgoing::Event(event) => visible(event),
+      Outgoing::Reset(_) => true,
+    })
+    .map(|outgoing| Ok(to_sse_event(&outgoing)));
+
+  Ok(Sse::new(stream).keep_alive(KeepAlive::new().interval(Duration::from_secs(15))))
+}
+
+/// What is sent to a client, before filtering the namespaces it may read
+#[derive(Debug)]
+enum Outgoing {
+  Event(Box<StoreEvent>),
+  /// the events the client missed are no longer available, it should list everything again
+  Reset(u64),
+}
+
+/// The events after `last_event_id`, followed by the events published on `live_events`, which is subscribed to first.
+/// Live events are only skipped when they were already sent from the history, or are covered by the reset,
+/// so a new client receives every event since it subscribed.
+async fn outgoing_events<S: Store>(store: &S, live_events: broadcast::Receiver<StoreEvent>, last_event_id: Option<u64>) -> StoreResult<impl Stream<Item = Outgoing>> {
+  let mut initial_events = Vec::new();
+  let mut last_revision = 0;
+  if let Some(last_event_id) = last_event_id {
+    match store.events_since(last_event_id).await? {
+      Some(missed_events) => {
+        last_revision = missed_events.last().map(|event| event.revision).unwrap_or(last_event_id);
+        initial_events.extend(missed_events.into_iter().map(|event| Outgoing::Event(Box::new(event))));
+      }
+      None => {
+        println!("Events since revision {} are no longer available, sending reset", last_event_id);
+        // the client lists everything after receiving the reset, which includes the changes up to this revision
+        last_revision = store.revision().await?;
+        initial_events.push(Outgoing::Reset(last_revision));
+      }
+    }
+  }
+
+  // a lagging subscriber ends the stream, the client then resumes from its Last-Event-ID
+  let live_events = BroadcastStream::new(live_events)
+    .take_while(|event| event.is_ok())
+    .filter_map(move |event| event.ok().filter(|event| event.revision > last_revision))
+    .map(|event| Outgoing::Event(Box::new(event)));
+
+  Ok(tokio_stream::iter(initial_events).chain(live_events))
+}
+
+fn to_http_error(error: NamespaceError) -> (StatusCode, String) {
+  let status = match error {
+    NamespaceError::Invalid(_) => StatusCode::BAD_REQUEST,
+    NamespaceError::Unauthenticated(_) => StatusCode::UNAUTHORIZED,
+    NamespaceError::PermissionDenied(_) => StatusCode::FORBIDDEN,
+  };
+  (status, error.to_string())
+}
+
+fn to_sse_event(outgoing: &Outgoing) -> Event {
+  match outgoing {
+    Outgoing::Event(event) => Event::default()
+      .id(event.revision.to_string())
+      .event(event.name())
+      .data(serde_json::to_string(event).unwrap_or_default()),
+    Outgoing::Reset(revision) => Event::default()
+      .id(revision.to_string())
+      .event("reset")
+      .data(format!("{{\"revision\":{}}}", revision)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::BTreeMap;
+
+  use super::*;
+  use crate::store::inmemory::InMemoryStore;
+  use crate::store::model::{GSFHealth, GSFInstance, InstanceType, RelayStatus};
+
+  fn hub(id: &str) -> GSFInstance {
+    GSFInstance {
+      id: id.to_string(),
+      instance_type: InstanceType::Hub,
+      name: id.to_string(),
+      version: "0.1".to_string(),
+      host: "10.0.0.1".to_string(),
+      port: "50051".to_string(),
+      repositories: "".to_string(),
+      relay_host: "".to_string(),
+      relay_port: "".to_string(),
+      health: GSFHealth::default(),
+      relay_status: RelayStatus::default(),
+      labels: BTreeMap::new(),
+      resource_version: 0,
+    }
+  }
+
+  async fn next_revision(stream: &mut (impl Stream<Item = Outgoing> + Unpin)) -> u64 {
+    match tokio::time::timeout(Duration::from_secs(1), stream.next()).await {
+      Ok(Some(Outgoing::Event(event))) => event.revision,
+      other => panic!("expected an event, got {:?}", other),
+    }
+  }
+
+  #[tokio::test]
+  async fn resumes_after_the_last_event_id() {
+    let store = InMemoryStore::new();
+    for id in ["hub-1", "hub-2", "hub-3"] {
+      store.add_instance("default", hub(id), None).await.unwrap();
+    }
+    let stream = outgoing_events(&store, store.subscribe(), Some(1)).await.unwrap();
+    tokio::pin!(stream);
+    assert_eq!(next_revision(&mut stream).await, 2);
+    assert_eq!(next_revision(&mut stream).await, 3);
+    store.add_instance("default", hub("hub-4"), None).await.unwrap();
+    assert_eq!(next_revision(&mut stream).await, 4);
+  }
+
+  #[tokio::test]
+  async fn resets_when_the_missed_events_are_unknown() {
+    let store = InMemoryStore::new();
+    store.add_instance("default", hub("hub-1"), None).await.unwrap();
+    // a revision from before a restart of the Discovery Server
+    let stream = outgoing_events(&store, store.subscribe(), Some(42)).await.unwrap();
+    tokio::pin!(stream);
+    assert!(matches!(stream.next().await, Some(Outgoing::Reset(1))));
+    store.add_instance("default", hub("hub-2"), None).await.unwrap();
+    assert_eq!(next_revision(&mut stream).await, 2);
+  }
+
+  #[tokio::test]
+  async fn sends_events_published_while_a_new_client_connects() {
+    let store = InMemoryStore::new();
+    let live_events = store.subscribe();
+    // published after subscribing, but before the stream is set up
+    store.add_instance("default", hub("hub-1"), None).await.unwrap();
+    let stream = outgoing_events(&store, live_events, None).await.unwrap();
+    tokio::pin!(stream);
+    assert_eq!(next_revision(&mut stream).await, 1);
+  }
+}
@@ -0,0 +1 @@
+pub mod events;
\ No newline at end of file
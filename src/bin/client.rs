@@ -1,37 +1,39 @@
-use std::error::Error;
 use std::future;
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
 use autometrics::{autometrics, prometheus_exporter};
 use axum::Router;
 use axum::routing::get;
-use clap::{Parser, Subcommand};
-use opentelemetry::{global, propagation::Injector};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use opentelemetry::{
     trace::{ TraceContextExt, Tracer},
-    Context, KeyValue,
+    Context,
 };
 
-use tonic::transport::Channel;
+use std::time::Duration;
+use tokio::task::JoinSet;
 use tracing::Instrument;
 
-use gitstafette_discovery::{
-    discovery_client::DiscoveryClient, GetHubsRequest, GitstafetteHub, RegisterHubRequest,GitstafetteServer, GetServersRequest, RegisterServerRequest
-};
-
-use gitstafette_info:: {
-    info_client::InfoClient, GetInfoRequest, InstanceType
-};
+use gsf_discovery::{ClientConfig, ClientError, DiscoveryClient, MirrorConfig, RegistryMirror, TlsConfig};
+use gitstafette_discovery::{ChangeType, FleetInstanceInfo, GitstafetteHub, GitstafetteInstance, GitstafetteServer, InstanceChange};
 
-// https://timvw.be/2022/04/28/notes-on-using-grpc-with-rust-and-tonic/
-#[allow(clippy::derive_partial_eq_without_eq)] // tonic don't derive Eq for generated types. We shouldn't manually change it.
-#[path = "gitstafette_discovery.rs"]
-pub mod gitstafette_discovery;
+use gitstafette_info::InstanceType;
+use crate::output::format::{print_list, OutputFormat, SortBy};
+use crate::output::view::{HubView, InstanceView, ServerView};
+use crate::sidecar::advertise::{Advertise, DownwardApi};
+use crate::sidecar::info::{InfoSource, StaticInfo};
+use crate::sidecar::registration::sync_local_status_to_discovery_server;
+use crate::sidecar::render::{parse_signal, render_loop, render_once, Reload, RenderConfig};
+use crate::sidecar::status::{healthz, readyz, SidecarStatus, SyncStatus};
+use crate::sidecar::target::{check_unique_names, read_targets_file, InfoTarget, DEFAULT_INFO_PATH};
+use crate::sidecar::supervisor::run_supervised;
 
-#[allow(clippy::derive_partial_eq_without_eq)] // tonic don't derive Eq for generated types. We shouldn't manually change it.
-#[path = "gitstafette_info.rs"]
-pub mod gitstafette_info;
+pub use gsf_discovery::proto::{gitstafette_discovery, gitstafette_info};
 
 mod otel;
+mod output;
+mod sidecar;
+mod retry;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -50,6 +52,38 @@ struct Cli {
     #[arg(long, default_value = "http")]
     protocol: String,
 
+    /// Gitstafette Discovery Server address as protocol://host:port, instead of --hostname, --port and --protocol.
+    /// Can be repeated, calls go to the first healthy server and fail over to the next one on errors
+    #[arg(long = "endpoint")]
+    endpoints: Vec<String>,
+
+    /// register with, and deregister from, every Discovery Server instead of a single healthy one
+    #[arg(long)]
+    register_all: bool,
+
+    /// PEM file with the CA certificate to verify https Discovery Servers with, instead of the system roots
+    #[arg(long)]
+    ca_cert: Option<String>,
+
+    /// name to verify the certificate of https Discovery Servers against, instead of their host
+    #[arg(long)]
+    tls_domain: Option<String>,
+
+    /// PEM file with the client certificate for mutual TLS, requires --client-key
+    #[arg(long, requires = "client_key")]
+    client_cert: Option<String>,
+
+    #[arg(long, requires = "client_cert")]
+    client_key: Option<String>,
+
+    /// bearer token sent with every request to the Discovery Server
+    #[arg(long)]
+    token: Option<String>,
+
+    /// namespace to register in and look up from, the `default` namespace of the Discovery Server when not set
+    #[arg(short, long)]
+    namespace: Option<String>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -74,14 +108,53 @@ enum Commands {
         relay_host: String,
         #[arg(long)]
         relay_port: String,
+        /// label as key=value, e.g. env=prod (can be repeated)
+        #[arg(long = "label", value_parser = parse_label)]
+        labels: Vec<(String, String)>,
+        /// only replace the registration while it is at this resource version, fails with ABORTED otherwise
+        #[arg(long)]
+        expected_version: Option<u64>,
     },
     // retrieves all registered Gitstafette Hubs
     GetHubs {
         #[arg(short, long, default_value = "true")]
         print: bool,
+        /// also return hubs that failed their health probes
+        #[arg(long)]
+        include_unhealthy: bool,
+        /// only hubs with labels that match this selector, e.g. `env=prod,zone in (a,b),!canary`
+        #[arg(short = 'l', long)]
+        selector: Option<String>,
+        /// wait until the hubs changed since this index, printed to stderr by the previous call, 0 returns right away
+        #[arg(long)]
+        wait_index: Option<u64>,
+        /// longest time in milliseconds to wait with --wait-index (0 uses the server default)
+        #[arg(long, default_value = "0", requires = "wait_index")]
+        wait_timeout_ms: u64,
+        #[arg(short, long, value_enum, default_value = "table")]
+        output: OutputFormat,
+        #[arg(long, value_enum, default_value = "name")]
+        sort_by: SortBy,
     },
     // retrieve all registered Gitstafette Servers
-    GetServers,
+    GetServers {
+        /// also return servers that failed their health probes
+        #[arg(long)]
+        include_unhealthy: bool,
+        /// only servers with labels that match this selector, e.g. `env=prod,zone in (a,b),!canary`
+        #[arg(short = 'l', long)]
+        selector: Option<String>,
+        /// wait until the servers changed since this index, printed to stderr by the previous call, 0 returns right away
+        #[arg(long)]
+        wait_index: Option<u64>,
+        /// longest time in milliseconds to wait with --wait-index (0 uses the server default)
+        #[arg(long, default_value = "0", requires = "wait_index")]
+        wait_timeout_ms: u64,
+        #[arg(short, long, value_enum, default_value = "table")]
+        output: OutputFormat,
+        #[arg(long, value_enum, default_value = "name")]
+        sort_by: SortBy,
+    },
     /// registers a Gitstafette Server
     RegisterServer {
         #[arg(long)]
@@ -96,19 +169,292 @@ enum Commands {
         port: String,
         #[arg(long)]
         repositories: String,
+        /// label as key=value, e.g. env=prod (can be repeated)
+        #[arg(long = "label", value_parser = parse_label)]
+        labels: Vec<(String, String)>,
+        /// only replace the registration while it is at this resource version, fails with ABORTED otherwise
+        #[arg(long)]
+        expected_version: Option<u64>,
+    },
+
+    /// deregisters a Gitstafette Hub
+    DeregisterHub {
+        #[arg(long)]
+        id: String,
+    },
+    /// deregisters a Gitstafette Server
+    DeregisterServer {
+        #[arg(long)]
+        id: String,
+    },
+
+    /// registers a Gitstafette instance of any type
+    RegisterInstance {
+        #[arg(long = "type", value_enum)]
+        instance_type: InstanceTypeArg,
+        #[arg(long)]
+        id: String,
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        version: String,
+        #[arg(long)]
+        host: String,
+        #[arg(long)]
+        port: String,
+        #[arg(long, default_value = "")]
+        repositories: String,
+        #[arg(long, default_value = "")]
+        relay_host: String,
+        #[arg(long, default_value = "")]
+        relay_port: String,
+        /// label as key=value, e.g. env=prod (can be repeated)
+        #[arg(long = "label", value_parser = parse_label)]
+        labels: Vec<(String, String)>,
+        /// only replace the registration while it is at this resource version, fails with ABORTED otherwise
+        #[arg(long)]
+        expected_version: Option<u64>,
+    },
+    /// retrieves the registered Gitstafette instances of every type, or of the given types
+    GetInstances {
+        /// only instances of this type (can be repeated)
+        #[arg(long = "type", value_enum)]
+        instance_types: Vec<InstanceTypeArg>,
+        /// also return instances that failed their health probes
+        #[arg(long)]
+        include_unhealthy: bool,
+        /// only instances with labels that match this selector, e.g. `env=prod,zone in (a,b),!canary`
+        #[arg(short = 'l', long)]
+        selector: Option<String>,
+        #[arg(short, long, value_enum, default_value = "table")]
+        output: OutputFormat,
+        #[arg(long, value_enum, default_value = "name")]
+        sort_by: SortBy,
+    },
+    /// deregisters a Gitstafette instance of any type
+    DeregisterInstance {
+        #[arg(long = "type", value_enum)]
+        instance_type: InstanceTypeArg,
+        #[arg(long)]
+        id: String,
+    },
+
+    /// lists the namespaces the token may read, with the number of instances in each
+    GetNamespaces,
+
+    /// prints the registrations that changed since a revision, and the revision to continue from
+    GetChanges {
+        /// revision printed by the previous call, 0 for all changes since the Discovery Server started
+        #[arg(long, default_value = "0")]
+        since: u64,
     },
 
-    /// loops asking a local Gistafette Info server and registers it to the Discovery Server
+    /// retrieves the Info of every registered Gitstafette instance through the Discovery Server
+    FleetInfo {
+        /// timeout in milliseconds for each instance (0 uses the server default)
+        #[arg(long, default_value = "0")]
+        timeout_ms: u32,
+    },
+
+    /// loops asking local Gistafette Info servers and registers them to the Discovery Server
     InfoRegistrationLoop {
+        #[arg(long, requires = "info_port")]
+        info_host: Option<String>,
+        #[arg(long, requires = "info_host")]
+        info_port: Option<String>,
+        #[arg(long, default_value = "http")]
+        info_protocol: String,
+        /// id to register the local instance of --info-host and --info-port with, defaults to the host:port it is advertised with
+        #[arg(long, default_value = "")]
+        id: String,
+        #[command(flatten)]
+        info_source: InfoSourceArgs,
+        /// additional local instance to poll, as `[id@][mode+][protocol://]host:port[/path]` (can be repeated),
+        /// with mode `json` or `health` like --info-mode
+        #[arg(long = "target")]
+        targets: Vec<String>,
+        /// file with one local instance to poll per line, in the same format as --target
+        #[arg(long)]
+        targets_file: Option<String>,
+        /// host to register every local instance with, instead of the one its Info service reports
+        #[arg(long)]
+        advertise_host: Option<String>,
+        /// port to register the local instance of --info-host and --info-port with, instead of the one its Info service reports
+        #[arg(long)]
+        advertise_port: Option<String>,
+        /// read POD_IP, POD_NAME, POD_NAMESPACE and NODE_NAME from the Kubernetes downward API,
+        /// to replace local hosts such as localhost, as id of the local instance when --id is not set,
+        /// and as `namespace` and `node` labels
+        #[arg(long)]
+        downward_api: bool,
+        /// label to register every local instance with, as key=value (can be repeated)
+        #[arg(long = "label", value_parser = parse_label)]
+        labels: Vec<(String, String)>,
+        /// seconds between registrations while the local instance is available
+        #[arg(long, default_value = "10", value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+        /// maximum seconds between attempts while the local instance or the Discovery Server is unavailable
+        #[arg(long, default_value = "60", value_parser = clap::value_parser!(u64).range(1..))]
+        max_backoff: u64,
+        /// port of the webserver with the metrics and the /healthz and /readyz endpoints
+        #[arg(long, default_value = "8082")]
+        web_port: String,
+        /// seconds since the last successful Info poll and registration after which /readyz fails
+        #[arg(long, default_value = "60")]
+        ready_max_age: u64,
+    },
+
+    /// renders the registered hubs and servers with a Handlebars template to a file,
+    /// whenever they change, and optionally reloads the process that reads it
+    Render {
+        /// Handlebars template, with `hubs` and `servers` as context
+        #[arg(long)]
+        template: String,
+        /// file to write, it is replaced atomically
+        #[arg(long)]
+        output: String,
+        /// seconds between polls of the Discovery Server
+        #[arg(long, default_value = "10", value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+        /// maximum seconds between attempts while the Discovery Server is unavailable
+        #[arg(long, default_value = "60", value_parser = clap::value_parser!(u64).range(1..))]
+        max_backoff: u64,
+        /// also render hubs and servers that failed their health probes
+        #[arg(long)]
+        include_unhealthy: bool,
+        /// only render hubs and servers with labels that match this selector, e.g. `env=prod,zone in (a,b),!canary`
+        #[arg(short = 'l', long)]
+        selector: Option<String>,
+        /// signal to send after the output changed, such as HUP, requires --reload-pid or --reload-pid-file
+        #[arg(long)]
+        reload_signal: Option<String>,
+        #[arg(long)]
+        reload_pid: Option<i32>,
+        #[arg(long)]
+        reload_pid_file: Option<String>,
+        /// shell command to run after the output changed
         #[arg(long)]
+        reload_command: Option<String>,
+        /// render once and exit, for example in an init container
+        #[arg(long)]
+        once: bool,
+        /// file to keep the last registry in, rendered while no Discovery Server is available, also after a restart
+        #[arg(long)]
+        snapshot_file: Option<String>,
+    },
+
+    /// runs a Gitstafette Server or Hub as child process (the command after `--`),
+    /// registers it while its Info server reports it is alive, and deregisters it when it stops
+    Run {
+        #[arg(long, default_value = "127.0.0.1")]
         info_host: String,
         #[arg(long)]
         info_port: String,
-        #[arg(long)]
+        #[arg(long, default_value = "http")]
         info_protocol: String,
+        /// id to register the child with, defaults to the host:port it is advertised with
+        #[arg(long, default_value = "")]
+        id: String,
+        #[command(flatten)]
+        info_source: InfoSourceArgs,
+        /// host to register the child with, instead of the one its Info service reports
+        #[arg(long)]
+        advertise_host: Option<String>,
+        /// port to register the child with, instead of the one its Info service reports
+        #[arg(long)]
+        advertise_port: Option<String>,
+        /// read POD_IP, POD_NAME, POD_NAMESPACE and NODE_NAME from the Kubernetes downward API,
+        /// to replace local hosts such as localhost, as id of the child when --id is not set,
+        /// and as `namespace` and `node` labels
+        #[arg(long)]
+        downward_api: bool,
+        /// label to register the child with, as key=value (can be repeated)
+        #[arg(long = "label", value_parser = parse_label)]
+        labels: Vec<(String, String)>,
+        /// seconds between registrations while the child is available
+        #[arg(long, default_value = "10", value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+        /// maximum seconds between attempts while the child or the Discovery Server is unavailable
+        #[arg(long, default_value = "60", value_parser = clap::value_parser!(u64).range(1..))]
+        max_backoff: u64,
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
     },
 }
 
+/// How the sidecar learns about the local instance, for instances without the gRPC Info service
+#[derive(Args)]
+struct InfoSourceArgs {
+    /// grpc: the Info service, json: an HTTP endpoint that returns the Info as JSON,
+    /// health: the gRPC health service, with the instance described by the --static-* flags
+    #[arg(long, value_enum, default_value = "grpc")]
+    info_mode: InfoMode,
+    /// path of the HTTP endpoint for --info-mode json
+    #[arg(long, default_value = DEFAULT_INFO_PATH)]
+    info_path: String,
+    /// instance type for --info-mode health and health+ targets
+    #[arg(long, value_enum, default_value = "server")]
+    static_type: InstanceTypeArg,
+    /// name for --info-mode health and health+ targets (default: the target)
+    #[arg(long)]
+    static_name: Option<String>,
+    /// version for --info-mode health and health+ targets
+    #[arg(long, default_value = "unknown")]
+    static_version: String,
+    /// repositories for --info-mode health and health+ targets
+    #[arg(long)]
+    static_repositories: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum InfoMode {
+    Grpc,
+    Json,
+    Health,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum InstanceTypeArg {
+    Relay,
+    Server,
+    Client,
+    Hub,
+    Discovery,
+    Explorer,
+}
+
+impl From<InstanceTypeArg> for InstanceType {
+    fn from(instance_type: InstanceTypeArg) -> Self {
+        match instance_type {
+            InstanceTypeArg::Relay => InstanceType::Relay,
+            InstanceTypeArg::Server => InstanceType::Server,
+            InstanceTypeArg::Client => InstanceType::Client,
+            InstanceTypeArg::Hub => InstanceType::Hub,
+            InstanceTypeArg::Discovery => InstanceType::Discovery,
+            InstanceTypeArg::Explorer => InstanceType::Explorer,
+        }
+    }
+}
+
+impl InfoSourceArgs {
+    fn static_info(&self) -> StaticInfo {
+        StaticInfo {
+            instance_type: self.static_type.into(),
+            name: self.static_name.clone(),
+            version: self.static_version.to_string(),
+            repositories: self.static_repositories.clone(),
+        }
+    }
+
+    fn source(&self) -> InfoSource {
+        match self.info_mode {
+            InfoMode::Grpc => InfoSource::Grpc,
+            InfoMode::Json => InfoSource::Http { path: self.info_path.to_string() },
+            InfoMode::Health => InfoSource::Health(self.static_info()),
+        }
+    }
+}
+
 // #[autometrics]
 #[tracing::instrument]
 async fn parse_cli() -> Result<(), Box<dyn std::error::Error>> {
@@ -117,70 +463,267 @@ async fn parse_cli() -> Result<(), Box<dyn std::error::Error>> {
     prometheus_exporter::init();
 
     otel::tracing::init_tracing_subscriber("client".to_string());
+    // the library reports failovers and failed refreshes as tracing events instead of printing them
+    let _ = tracing_subscriber::fmt().with_writer(std::io::stderr).with_max_level(tracing::Level::WARN).try_init();
 
-    let server = format!("{}://{}:{}", cli.protocol, cli.hostname, cli.port);
+    let addresses = if cli.endpoints.is_empty() {
+        vec![format!("{}://{}:{}", cli.protocol, cli.hostname, cli.port)]
+    } else {
+        cli.endpoints.clone()
+    };
 
     // You can check the value provided by positional arguments, or option arguments
-    println!("Discovery Server Address={server}");
+    eprintln!("Discovery Server Address={}", addresses.join(","));
 
-    let mut discovery_client: DiscoveryClient<tonic::transport::Channel> = DiscoveryClient::connect(server).await?;
+    // the client connects on first use, so the info registration loop can start before the Discovery Server is available
+    let discovery_client = DiscoveryClient::new(client_config(&cli, addresses)?)?;
     let span = otel::tracing::create_client_span( "GSF-Discovery/CLI".to_string(), "parse_cli".to_string());
     let cx = Context::current_with_span(span);
 
     // You can check for the existence of subcommands, and if found use their
     // matches just as you would the top level cmd
     match &cli.command {
-        Some(Commands::RegisterHub{id, name, version, host, port, repositories, relay_host, relay_port }) => {
+        Some(Commands::RegisterHub{id, name, version, host, port, repositories, relay_host, relay_port, labels, expected_version }) => {
             println!("registering hub: {}", *id);
             // create request
-            let request= RegisterHubRequest {
-                hub: Some(GitstafetteHub {
-                    id: id.to_string(),
-                    name: name.to_string(),
-                    version: version.to_string(),
-                    host: host.to_string(),
-                    port: port.to_string(),
-                    repositories: repositories.to_string(),
-                    relay_host: relay_host.to_string(),
-                    relay_port: relay_port.to_string(),
-                }),
+            let hub = GitstafetteHub {
+                id: id.to_string(),
+                name: name.to_string(),
+                version: version.to_string(),
+                host: host.to_string(),
+                port: port.to_string(),
+                repositories: repositories.to_string(),
+                relay_host: relay_host.to_string(),
+                relay_port: relay_port.to_string(),
+                health: None,
+                relay_status: gitstafette_discovery::RelayStatus::Unknown.into(),
+                labels: labels.iter().cloned().collect(),
+                resource_version: 0,
             };
-            register_hub(&mut discovery_client, request, &cx).await;
+            match expected_version {
+                Some(expected_version) => {
+                    let resource_version = discovery_client.update_hub(hub, *expected_version, &cx).await?;
+                    println!("updated hub: {} (resource version {})", *id, resource_version);
+                }
+                None => {
+                    discovery_client.register_hub(hub, &cx).await?;
+                    println!("registered hub: {}", *id);
+                }
+            }
         }
-        Some(Commands::GetHubs{print}) => {
+        Some(Commands::GetHubs{print, include_unhealthy, selector, wait_index, wait_timeout_ms, output, sort_by}) => {
+            let selector = selector.as_deref().unwrap_or_default();
+            let hubs = match wait_index {
+                Some(wait_index) => {
+                    let response = discovery_client.get_hubs_blocking(selector, *include_unhealthy, *wait_index, Duration::from_millis(*wait_timeout_ms), &cx).await?;
+                    eprintln!("index: {}", response.index);
+                    response.hubs
+                }
+                None => discovery_client.get_hubs_matching(selector, *include_unhealthy, &cx).await?,
+            };
             if *print {
-                println!("retrieving hubs");
-                get_hubs(&mut discovery_client, &cx).await;
+                print_list(hubs.into_iter().map(HubView::from).collect(), *output, *sort_by)?;
             }
         }
-        Some(Commands::GetServers) => {
-            println!("retrieving servers");
-            get_servers(&mut discovery_client, &cx).await;
+        Some(Commands::GetServers{include_unhealthy, selector, wait_index, wait_timeout_ms, output, sort_by}) => {
+            let selector = selector.as_deref().unwrap_or_default();
+            let servers = match wait_index {
+                Some(wait_index) => {
+                    let response = discovery_client.get_servers_blocking(selector, *include_unhealthy, *wait_index, Duration::from_millis(*wait_timeout_ms), &cx).await?;
+                    eprintln!("index: {}", response.index);
+                    response.servers
+                }
+                None => discovery_client.get_servers_matching(selector, *include_unhealthy, &cx).await?,
+            };
+            print_list(servers.into_iter().map(ServerView::from).collect(), *output, *sort_by)?;
         }
-        Some(Commands::RegisterServer { id, name, version, host, port, repositories }   ) => {
+        Some(Commands::RegisterServer { id, name, version, host, port, repositories, labels, expected_version }   ) => {
             println!("registering server: {}", *id);
             // create request
-            let request= RegisterServerRequest {
-                server: Some(GitstafetteServer {
-                    id: id.to_string(),
-                    name: name.to_string(),
-                    version: version.to_string(),
-                    host: host.to_string(),
-                    port: port.to_string(),
-                    repositories: repositories.to_string(),
-                }),
+            let server = GitstafetteServer {
+                id: id.to_string(),
+                name: name.to_string(),
+                version: version.to_string(),
+                host: host.to_string(),
+                port: port.to_string(),
+                repositories: repositories.to_string(),
+                health: None,
+                labels: labels.iter().cloned().collect(),
+                resource_version: 0,
             };
-            register_server(&mut discovery_client, request, &cx).await;
+            match expected_version {
+                Some(expected_version) => {
+                    let resource_version = discovery_client.update_server(server, *expected_version, &cx).await?;
+                    println!("updated server: {} (resource version {})", *id, resource_version);
+                }
+                None => {
+                    discovery_client.register_server(server, &cx).await?;
+                    println!("registered server: {}", *id);
+                }
+            }
         }
-        Some(Commands::InfoRegistrationLoop { info_host, info_port, info_protocol }) => {
-            println!("Starting info registration loop");
-            // for kubernetes health checks
-            // TODO: make webserver configurable
+        Some(Commands::RegisterInstance { instance_type, id, name, version, host, port, repositories, relay_host, relay_port, labels, expected_version }) => {
+            let instance_type = InstanceType::from(*instance_type);
+            println!("registering {}: {}", instance_type.as_str_name().to_lowercase(), *id);
+            let instance = GitstafetteInstance {
+                id: id.to_string(),
+                instance_type: instance_type.into(),
+                name: name.to_string(),
+                version: version.to_string(),
+                host: host.to_string(),
+                port: port.to_string(),
+                repositories: repositories.to_string(),
+                relay_host: relay_host.to_string(),
+                relay_port: relay_port.to_string(),
+                health: None,
+                relay_status: gitstafette_discovery::RelayStatus::Unknown.into(),
+                labels: labels.iter().cloned().collect(),
+                resource_version: 0,
+            };
+            match expected_version {
+                Some(expected_version) => {
+                    let resource_version = discovery_client.update_instance(instance, *expected_version, &cx).await?;
+                    println!("updated {}: {} (resource version {})", instance_type.as_str_name().to_lowercase(), *id, resource_version);
+                }
+                None => {
+                    discovery_client.register_instance(instance, &cx).await?;
+                    println!("registered {}: {}", instance_type.as_str_name().to_lowercase(), *id);
+                }
+            }
+        }
+        Some(Commands::GetInstances { instance_types, include_unhealthy, selector, output, sort_by }) => {
+            let instance_types: Vec<InstanceType> = instance_types.iter().map(|instance_type| (*instance_type).into()).collect();
+            let instances = discovery_client.list_instances_matching(&instance_types, selector.as_deref().unwrap_or_default(), *include_unhealthy, &cx).await?;
+            print_list(instances.into_iter().map(InstanceView::from).collect(), *output, *sort_by)?;
+        }
+        Some(Commands::DeregisterInstance { instance_type, id }) => {
+            let instance_type = InstanceType::from(*instance_type);
+            println!("deregistering {}: {}", instance_type.as_str_name().to_lowercase(), *id);
+            discovery_client.deregister_instance(instance_type, id, &cx).await?;
+        }
+        Some(Commands::GetNamespaces) => {
+            println!("{:<32} INSTANCES", "NAME");
+            for namespace in discovery_client.list_namespaces(&cx).await? {
+                println!("{:<32} {}", namespace.name, namespace.instances);
+            }
+        }
+        Some(Commands::GetChanges { since }) => {
+            let changes = discovery_client.get_changes(*since, &cx).await?;
+            if changes.reset {
+                println!("changes since revision {} are no longer available, list all instances again", *since);
+            }
+            for change in &changes.changes {
+                print_change(change);
+            }
+            println!("revision: {}", changes.revision);
+        }
+        Some(Commands::FleetInfo { timeout_ms }) => {
+            println!("retrieving fleet info");
+            print_fleet_info(&discovery_client.get_fleet_info(*timeout_ms, &cx).await?);
+        }
+        Some(Commands::DeregisterHub { id }) => {
+            println!("deregistering hub: {}", *id);
+            discovery_client.deregister_hub(id, &cx).await?;
+        }
+        Some(Commands::DeregisterServer { id }) => {
+            println!("deregistering server: {}", *id);
+            discovery_client.deregister_server(id, &cx).await?;
+        }
+        Some(Commands::InfoRegistrationLoop { info_host, info_port, info_protocol, id, info_source, targets, targets_file, advertise_host, advertise_port, downward_api, labels, interval, max_backoff, web_port, ready_max_age }) => {
+            let downward_api = downward_api.then(DownwardApi::from_env);
+            let labels = target_labels(labels, downward_api.as_ref());
+            let mut info_targets = Vec::new();
+            if let (Some(info_host), Some(info_port)) = (info_host, info_port) {
+                let advertise = Advertise::new(advertise_host.clone(), advertise_port.clone(), downward_api.as_ref());
+                let id = default_id(id, downward_api.as_ref());
+                info_targets.push(InfoTarget { host: info_host.to_string(), port: info_port.to_string(), protocol: info_protocol.to_string(), id, advertise, source: info_source.source(), labels: labels.clone() });
+            }
+            let mut additional_targets = Vec::new();
+            for target in targets {
+                additional_targets.push(target.parse::<InfoTarget>()?);
+            }
+            if let Some(targets_file) = targets_file {
+                additional_targets.extend(read_targets_file(targets_file)?);
+            }
+            for mut target in additional_targets {
+                // the instances share the pod, but not the port
+                target.advertise = Advertise::new(advertise_host.clone(), None, downward_api.as_ref());
+                target.labels = labels.clone();
+                if let InfoSource::Health(_) = target.source {
+                    target.source = InfoSource::Health(info_source.static_info());
+                }
+                info_targets.push(target);
+            }
+            if info_targets.is_empty() {
+                return Err("no local instance to poll, use --info-host and --info-port, --target or --targets-file".into());
+            }
+            check_unique_names(&info_targets)?;
+
+            println!("Starting info registration loop for {} target(s)", info_targets.len());
+            let interval = Duration::from_secs(*interval);
+            let max_backoff = Duration::from_secs(*max_backoff);
+            // a loop is considered stuck when it did not attempt anything for longer than it can wait between attempts
+            let live_max_age = Duration::from_secs(*ready_max_age) + interval.max(max_backoff);
+
+            // every target has its own loop and status, so they are registered and deregistered independently
+            let mut loops = JoinSet::new();
+            let mut statuses = Vec::new();
+            for target in info_targets {
+                let status = SyncStatus::new(Duration::from_secs(*ready_max_age), live_max_age);
+                statuses.push((target.name(), status.clone()));
+                let discovery_client = discovery_client.clone();
+                loops.spawn(async move {
+                    let result = sync_local_status_to_discovery_server(&discovery_client, &target, interval, max_backoff, &status).await;
+                    (target.name(), result.map_err(|error| error.to_string()))
+                });
+            }
+            let status = SidecarStatus::new(statuses);
+            status.register_metrics();
 
-            let t1  = start_webserver();
-            let t2 =    sync_local_status_to_discovery_server(&mut discovery_client, info_host, info_port, info_protocol);
+            // for kubernetes health checks
+            let t1  = start_webserver(web_port, status);
+            let t2 = async {
+                while let Some(finished) = loops.join_next().await {
+                    println!("Info registration loop finished: {:?}", finished);
+                }
+            };
             let (r1, r2) = tokio::join!(t1, t2);
-            println!("Webserver and info registration loop finished: {:?}, {:?}", r1, r2);
+            println!("Webserver and info registration loops finished: {:?}, {:?}", r1, r2);
+        }
+        Some(Commands::Render { template, output, interval, max_backoff, include_unhealthy, selector, reload_signal, reload_pid, reload_pid_file, reload_command, once, snapshot_file }) => {
+            let signal = reload_signal.as_deref().map(parse_signal).transpose()?;
+            if signal.is_some() && reload_pid.is_none() && reload_pid_file.is_none() {
+                return Err("--reload-signal requires --reload-pid or --reload-pid-file".into());
+            }
+            let config = RenderConfig {
+                template: template.into(),
+                output: output.into(),
+                interval: Duration::from_secs(*interval),
+                max_backoff: Duration::from_secs(*max_backoff),
+                reload: Reload { signal, pid: *reload_pid, pid_file: reload_pid_file.clone(), command: reload_command.clone() },
+            };
+            let mirror = RegistryMirror::new(discovery_client, MirrorConfig {
+                refresh_interval: config.interval,
+                snapshot_file: snapshot_file.as_ref().map(Into::into),
+                include_unhealthy: *include_unhealthy,
+                label_selector: selector.clone(),
+            });
+            if *once {
+                render_once(&mirror, &config, &cx).await?;
+            } else {
+                render_loop(&mirror, &config, &cx).await?;
+            }
+        }
+        Some(Commands::Run { info_host, info_port, info_protocol, id, info_source, advertise_host, advertise_port, downward_api, labels, interval, max_backoff, command }) => {
+            let downward_api = downward_api.then(DownwardApi::from_env);
+            let labels = target_labels(labels, downward_api.as_ref());
+            let advertise = Advertise::new(advertise_host.clone(), advertise_port.clone(), downward_api.as_ref());
+            let id = default_id(id, downward_api.as_ref());
+            let target = InfoTarget { host: info_host.to_string(), port: info_port.to_string(), protocol: info_protocol.to_string(), id, advertise, source: info_source.source(), labels };
+            let exit_code = run_supervised(&discovery_client, &target, Duration::from_secs(*interval), Duration::from_secs(*max_backoff), command, &cx).await?;
+            cx.span().end();
+            opentelemetry::global::shutdown_tracer_provider();
+            std::process::exit(exit_code);
         }
         None => {}
     }
@@ -191,15 +734,67 @@ async fn parse_cli() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-struct MetadataMap<'a>(&'a mut tonic::metadata::MetadataMap);
+/// the configured id, or the pod from the downward API when it is not set,
+/// otherwise empty so the instance is registered as the host:port it is advertised with
+fn default_id(id: &str, downward_api: Option<&DownwardApi>) -> String {
+    if !id.is_empty() {
+        return id.to_string();
+    }
+    downward_api.and_then(DownwardApi::instance_id).unwrap_or_default()
+}
 
-impl<'a> Injector for MetadataMap<'a> {
-    /// Set a key and value in the MetadataMap.  Does nothing if the key or value are not valid inputs
-    fn set(&mut self, key: &str, value: String) {
-        if let Ok(key) = tonic::metadata::MetadataKey::from_bytes(key.as_bytes()) {
-            if let Ok(val) = tonic::metadata::MetadataValue::try_from(&value) {
-                self.0.insert(key, val);
-            }
+/// the labels of the downward API, overridden by the configured labels
+fn target_labels(labels: &[(String, String)], downward_api: Option<&DownwardApi>) -> BTreeMap<String, String> {
+    let mut target_labels = downward_api.map(DownwardApi::labels).unwrap_or_default();
+    target_labels.extend(labels.iter().cloned());
+    target_labels
+}
+
+/// parses a `key=value` label, with the characters label selectors accept
+fn parse_label(label: &str) -> Result<(String, String), String> {
+    let (key, value) = label.split_once('=').ok_or_else(|| format!("invalid label {:?}, expected key=value", label))?;
+    let valid = |text: &str| text.chars().all(|character| character.is_ascii_alphanumeric() || matches!(character, '-' | '_' | '.' | '/'));
+    if key.is_empty() || !valid(key) || !valid(value) {
+        return Err(format!("invalid label {:?}, keys and values consist of letters, digits, '-', '_', '.' and '/'", label));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// the connection settings of the client from the CLI, the certificates are read from their files
+fn client_config(cli: &Cli, endpoints: Vec<String>) -> Result<ClientConfig, Box<dyn std::error::Error>> {
+    let read = |path: &String| std::fs::read(path).map_err(|error| format!("failed to read {}: {}", path, error));
+    let identity = match (&cli.client_cert, &cli.client_key) {
+        (Some(certificate), Some(key)) => Some((read(certificate)?, read(key)?)),
+        _ => None,
+    };
+    let mut config = ClientConfig::new(endpoints);
+    config.tls = TlsConfig {
+        ca_certificate: cli.ca_cert.as_ref().map(read).transpose()?,
+        domain: cli.tls_domain.clone(),
+        identity,
+    };
+    config.token = cli.token.clone();
+    config.namespace = cli.namespace.clone();
+    config.register_all = cli.register_all;
+    Ok(config)
+}
+
+fn print_change(change: &InstanceChange) {
+    let change_type = ChangeType::try_from(change.change_type).map(|change_type| change_type.as_str_name()).unwrap_or("CHANGE_TYPE_UNKNOWN");
+    let Some(instance) = &change.instance else {
+        return;
+    };
+    let instance_type = InstanceType::try_from(instance.instance_type).map(|instance_type| instance_type.as_str_name()).unwrap_or("UNKNOWN");
+    println!("{} {} {} {} ({}) {}:{}", change.revision, change_type.trim_start_matches("CHANGE_TYPE_"), instance_type, instance.id, instance.name, instance.host, instance.port);
+}
+
+/// prints the result, or the error, per instance
+fn print_fleet_info(instances: &[FleetInstanceInfo]) {
+    for instance in instances {
+        let instance_type = InstanceType::try_from(instance.instance_type).map(|instance_type| instance_type.as_str_name()).unwrap_or("UNKNOWN");
+        match &instance.info {
+            Some(info) => println!("{} {} ({}) {}:{} [{}ms] INFO={:?}", instance_type, instance.id, instance.name, instance.host, instance.port, instance.latency_ms, info),
+            None => println!("{} {} ({}) {}:{} [{}ms] ERROR={}", instance_type, instance.id, instance.name, instance.host, instance.port, instance.latency_ms, instance.error),
         }
     }
 }
@@ -210,10 +805,9 @@ async fn handler() -> &'static str {
     "Hello, World!"
 }
 
-async fn start_webserver() {
-    // TODO: make port configurable
+async fn start_webserver(web_port: &String, status: SidecarStatus) {
     // Web server with Axum
-    let web_address = "0.0.0.0:8082".to_string();
+    let web_address = format!("0.0.0.0:{}", web_port);
     let web_addr: SocketAddr =web_address.parse().unwrap();
     println!("Metrics server listening on {}", web_addr);
     let app = Router::new()
@@ -221,7 +815,10 @@ async fn start_webserver() {
         .route(
             "/metrics",
             get(|| async { prometheus_exporter::encode_http_response() }),
-        );
+        )
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(status);
 
     // Start the server
     println!("Starting web server at {}", web_addr);
@@ -231,198 +828,17 @@ async fn start_webserver() {
         .expect("Web server failed");
 }
 
-#[autometrics]
-#[tracing::instrument]
-async fn sync_local_status_to_discovery_server(mut discovery_client: &mut DiscoveryClient<Channel>, info_host: &String, info_port: &String, info_protocol: &String) -> Result<(), Box<dyn Error>> {
-    let server = format!("{}://{}:{}", info_protocol, info_host, info_port);
-    println!("info client connected to: {}", server);
-    let mut info_client: InfoClient<tonic::transport::Channel> = InfoClient::connect(server).await?;
-
-    loop {
-        let span = otel::tracing::create_client_span( "GSF-Discovery/client".to_string(), "sync_local_status_to_discovery_server".to_string());
-        let cx = Context::current_with_span(span);
-
-        let info_request = GetInfoRequest { client_id: "myself".to_string(), client_endpoint: "127.0.0.1:50051".to_string() };
-        let mut request: tonic::Request<GetInfoRequest> = tonic::Request::new(info_request);
-
-        global::get_text_map_propagator(|propagator| {
-            propagator.inject_context(&cx, &mut MetadataMap(request.metadata_mut()))
-        });
-
-        let response = info_client.get_info(request);
-        let result = response.await;
-        if result.is_err() {
-            let status_code = result.unwrap_err().code();
-            println!("ERROR={:?}", status_code);
-            cx.span().add_event(
-                "Got response!".to_string(),
-                vec![KeyValue::new("status", status_code.to_string())],
-            );
-            continue;
-        } else {
-            println!("Got successful response!");
-            cx.span().add_event(
-                "Got response!".to_string(),
-                vec![KeyValue::new("status", "OK".to_string())],
-            );
-        }
-
-        let info = result.unwrap();
-
-        // depending on the response, we should register the server/hub? to the Discovery Server
-        if info.get_ref().alive {
-            let server_info_opt = info.get_ref().server.as_ref();
-            let relay_info_opt = info.get_ref().relay.as_ref();
-
-            cx.span().add_event("local service is alive".to_string(), vec![]);
-
-            if InstanceType::Hub == InstanceType::try_from(info.get_ref().instance_type).unwrap() {
-                println!("registering hub: {}", info.get_ref().name);
-                // create request
-
-                let mut hub = GitstafetteHub {
-                    id: "".to_string(),
-                    name: info.get_ref().name.to_string(),
-                    version: info.get_ref().version.to_string(),
-                    host: "".to_string(),
-                    port: "".to_string(),
-                    repositories: "".to_string(),
-                    relay_host: "".to_string(),
-                    relay_port: "".to_string(),
-                };
-
-                if let Some(server_info) = server_info_opt {
-                    hub.host = server_info.hostname.to_string();
-                    hub.port = server_info.port.to_string();
-                    if let Some(repositories) = server_info.repositories.as_ref() {
-                        hub.repositories = repositories.to_string();
-                    }
-                }
-                if let Some(relay_info) = relay_info_opt {
-                    hub.relay_host = relay_info.hostname.to_string();
-                    hub.relay_port = relay_info.port.to_string();
-                }
-
-                let request = RegisterHubRequest {
-                    hub: Some(hub),
-                };
-                register_hub(&mut discovery_client, request, &cx).await;
-                cx.span().add_event("registered hub".to_string(), vec![]);
-            } else {
-                println!("registering server: {}", info.get_ref().name);
-                // create request
-                let mut gsf_server = GitstafetteServer {
-                    id: "".to_string(),
-                    name: info.get_ref().name.to_string(),
-                    version: info.get_ref().version.to_string(),
-                    repositories: "".to_string(),
-                    host: "".to_string(),
-                    port: "".to_string(),
-                };
-
-                if let Some(server_info) = server_info_opt {
-                    gsf_server.host = server_info.hostname.to_string();
-                    gsf_server.port = server_info.port.to_string();
-                    if let Some(repositories) = server_info.repositories.as_ref() {
-                        gsf_server.repositories = repositories.to_string();
-                    }
-                }
-
-                let request = RegisterServerRequest {
-                    server: Some(gsf_server),
-                };
-
-                register_server(&mut discovery_client, request, &cx).await;
-                cx.span().add_event("registered server".to_string(), vec![]);
-            }
-        }
-        cx.span().add_event("end of loop".to_string(), vec![]);
-        cx.span().end();
-        tokio::time::sleep(std::time::Duration::from_secs(10)).await;
-    }
-}
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("-----------------");
-    println!("Parsing CLI");
-    parse_cli().await
-}
-
-async fn register_hub(discovery_client: &mut DiscoveryClient<tonic::transport::Channel>, register_hub_request: RegisterHubRequest, cx: &Context) {
-    let mut request = tonic::Request::new(register_hub_request);
-    global::get_text_map_propagator(|propagator| {
-        propagator.inject_context(&cx, &mut MetadataMap(request.metadata_mut()))
-    });
-
-    let response = discovery_client.register_hub(request);
-    let something = response.await;
-    println!("RESPONSE={:?}", something.unwrap());
-}
-
-async fn get_hubs(discovery_client: &mut DiscoveryClient<tonic::transport::Channel>, cx: &Context) {
-    let mut request = tonic::Request::new(GetHubsRequest {
-        client_id: "test".to_string(),
-        name: "test".to_string(),
-        host: "test".to_string(),
-        port: "0".to_string(),
-    });
-
-    global::get_text_map_propagator(|propagator| {
-        propagator.inject_context(&cx, &mut MetadataMap(request.metadata_mut()))
-    });
-
-    let response = discovery_client.get_hubs(request);
-    let something = response.await;
-    println!("RESPONSE={:?}", something.unwrap());
-}
-
-/// queries the Discovery Server Endpoint for its registered Gitstafette Servers
-/// and returns them as a vector of GitstafetteServer
-/// # Arguments
-/// * `discovery_client` - DiscoveryClient
-/// # Returns
-/// * `Vec<GitstafetteServer>` - vector of GitstafetteServer
-/// # Example
-/// ```
-/// let servers = get_servers(&mut discovery_client).await;
-/// ```
-/// # Panics
-/// Panics if the Discovery Server is not reachable
-/// # Errors
-/// Returns an error if the Discovery Server is not reachable
-/// # Remarks
-/// This function is used by the Gitstafette Relay to retrieve the Gitstafette Servers
-/// from the Discovery Server
-async fn get_servers(discovery_client: &mut DiscoveryClient<tonic::transport::Channel>, cx: &Context) -> Vec<GitstafetteServer> {
-    let mut request = tonic::Request::new(GetServersRequest {
-        client_id: "test".to_string(),
-        name: "test".to_string(),
-        host: "test".to_string(),
-        port: "0".to_string(),
-    });
-
-    global::get_text_map_propagator(|propagator| {
-        propagator.inject_context(&cx, &mut MetadataMap(request.metadata_mut()))
-    });
-
-    let response = discovery_client.get_servers(request);
-    let something = response.await;
-    println!("RESPONSE={:?}", something.unwrap());
-    vec![]
+    eprintln!("-----------------");
+    eprintln!("Parsing CLI");
+    if let Err(error) = parse_cli().await {
+        // a readable message and a non-zero exit code, for scripts
+        match error.downcast_ref::<ClientError>() {
+            Some(ClientError::Rpc(status)) => eprintln!("Error: {:?} {}", status.code(), status.message()),
+            _ => eprintln!("Error: {}", error),
+        }
+        std::process::exit(1);
+    }
+    Ok(())
 }
-
-/// registers a Gitstafette Server
-/// # Arguments
-/// * `discovery_client` - DiscoveryClient
-/// * `register_server_request` - RegisterServerRequest
-async fn register_server(discovery_client: &mut DiscoveryClient<tonic::transport::Channel>, register_server_request: RegisterServerRequest, cx: &Context) {
-    let mut request: tonic::Request<RegisterServerRequest> = tonic::Request::new(register_server_request);
-
-    global::get_text_map_propagator(|propagator| {
-        propagator.inject_context(cx, &mut MetadataMap(request.metadata_mut()))
-    });
-    let response = discovery_client.register_server(request);
-    let something = response.await;
-    println!("RESPONSE={:?}", something.unwrap());
-}
\ No newline at end of file
@@ -0,0 +1,2 @@
+pub mod policy;
+pub mod scope;
\ No newline at end of file
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::namespace::scope::{validate, NamespaceError};
+
+/// the rules of the namespaces that are not listed in the policy
+const OTHER_NAMESPACES: &str = "*";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Access {
+  /// listing the instances
+  Read,
+  /// registering and deregistering instances, also allows reading
+  Write,
+}
+
+/// The bearer tokens that give access to a namespace.
+/// Without tokens for an access, anyone has that access.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NamespaceRules {
+  #[serde(default)]
+  pub read_tokens: Vec<String>,
+  #[serde(default)]
+  pub write_tokens: Vec<String>,
+}
+
+/// Who may access which namespace, read from a YAML file with the rules per namespace:
+///
+/// ```yaml
+/// production:
+///   write_tokens: [sidecar-token]
+///   read_tokens: [reader-token]
+/// "*":
+///   write_tokens: [admin-token]
+/// ```
+///
+/// `*` applies to the namespaces that are not listed. Without rules, a namespace is open to everyone.
+#[derive(Clone, Default)]
+pub struct NamespacePolicy {
+  namespaces: HashMap<String, NamespaceRules>,
+}
+
+// the service is traced with its fields, which must not include the tokens
+impl std::fmt::Debug for NamespacePolicy {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("NamespacePolicy").field("namespaces", &self.namespaces.keys().collect::<Vec<_>>()).finish()
+  }
+}
+
+impl NamespacePolicy {
+  pub fn from_file(path: &str) -> Result<NamespacePolicy, String> {
+    let contents = std::fs::read_to_string(path).map_err(|error| format!("failed to read {}: {}", path, error))?;
+    let namespaces: HashMap<String, NamespaceRules> =
+      serde_yaml::from_str(&contents).map_err(|error| format!("invalid namespace policy {}: {}", path, error))?;
+    for namespace in namespaces.keys().filter(|namespace| *namespace != OTHER_NAMESPACES) {
+      validate(namespace).map_err(|error| format!("invalid namespace policy {}: {}", path, error))?;
+    }
+    Ok(NamespacePolicy { namespaces })
+  }
+
+  /// The namespaces with their own rules
+  pub fn namespaces(&self) -> Vec<String> {
+    self.namespaces.keys().filter(|namespace| *namespace != OTHER_NAMESPACES).cloned().collect()
+  }
+
+  /// Checks the bearer token of a request gives the access to the namespace
+  pub fn authorize(&self, namespace: &str, access: Access, token: Option<&str>) -> Result<(), NamespaceError> {
+    let Some(rules) = self.namespaces.get(namespace).or_else(|| self.namespaces.get(OTHER_NAMESPACES)) else {
+      return Ok(());
+    };
+    let mut tokens = rules.write_tokens.iter().collect::<Vec<_>>();
+    if access == Access::Read {
+      // writers may read, but a namespace that only restricts writing is open for reading
+      if rules.read_tokens.is_empty() {
+        return Ok(());
+      }
+      tokens.extend(&rules.read_tokens);
+    }
+    if tokens.is_empty() {
+      return Ok(());
+    }
+    match token {
+      None => Err(NamespaceError::Unauthenticated(namespace.to_string())),
+      Some(token) if tokens.iter().any(|allowed| constant_time_eq(allowed.as_bytes(), token.as_bytes())) => Ok(()),
+      Some(_) => Err(NamespaceError::PermissionDenied(namespace.to_string())),
+    }
+  }
+}
+
+/// The token of an `authorization: Bearer <token>` header value
+pub fn bearer_token(authorization: Option<&str>) -> Option<&str> {
+  authorization
+    .and_then(|value| value.strip_prefix("Bearer "))
+    .map(str::trim)
+    .filter(|token| !token.is_empty())
+}
+
+/// compares the tokens without revealing how much of a guess is right through the time it takes
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+  a.len() == b.len() && a.iter().zip(b).fold(0, |difference, (a, b)| difference | (a ^ b)) == 0
+}
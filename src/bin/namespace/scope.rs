@@ -0,0 +1,63 @@
+use tonic::metadata::MetadataMap;
+use tonic::Status;
+
+/// metadata header with the namespace, for requests that do not set their namespace field
+pub const NAMESPACE_HEADER: &str = "x-gitstafette-namespace";
+/// namespace of the requests that set none
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NamespaceError {
+  Invalid(String),
+  /// the namespace requires a token, and the request has none
+  Unauthenticated(String),
+  /// the token of the request does not give access to the namespace
+  PermissionDenied(String),
+}
+
+impl std::fmt::Display for NamespaceError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      NamespaceError::Invalid(message) => write!(f, "invalid namespace: {}", message),
+      NamespaceError::Unauthenticated(namespace) => write!(f, "namespace {} requires a bearer token", namespace),
+      NamespaceError::PermissionDenied(namespace) => write!(f, "the token does not give access to namespace {}", namespace),
+    }
+  }
+}
+
+impl std::error::Error for NamespaceError {}
+
+impl From<NamespaceError> for Status {
+  fn from(error: NamespaceError) -> Self {
+    match error {
+      NamespaceError::Invalid(_) => Status::invalid_argument(error.to_string()),
+      NamespaceError::Unauthenticated(_) => Status::unauthenticated(error.to_string()),
+      NamespaceError::PermissionDenied(_) => Status::permission_denied(error.to_string()),
+    }
+  }
+}
+
+/// The namespace of a request: its namespace field, the namespace header, or the default namespace
+pub fn request_namespace(field: &str, metadata: &MetadataMap) -> Result<String, NamespaceError> {
+  let header = metadata.get(NAMESPACE_HEADER).and_then(|value| value.to_str().ok()).unwrap_or_default();
+  let namespace = [field.trim(), header.trim()]
+    .into_iter()
+    .find(|namespace| !namespace.is_empty())
+    .unwrap_or(DEFAULT_NAMESPACE);
+  validate(namespace)?;
+  Ok(namespace.to_string())
+}
+
+/// Namespaces are DNS labels, as in Kubernetes: at most 63 lowercase letters, digits and dashes, starting and ending with a letter or digit
+pub fn validate(namespace: &str) -> Result<(), NamespaceError> {
+  let valid_character = |character: char| character.is_ascii_lowercase() || character.is_ascii_digit() || character == '-';
+  if namespace.is_empty()
+    || namespace.len() > 63
+    || !namespace.chars().all(valid_character)
+    || namespace.starts_with('-')
+    || namespace.ends_with('-')
+  {
+    return Err(NamespaceError::Invalid(format!("{:?} is not a lowercase DNS label", namespace)));
+  }
+  Ok(())
+}
@@ -0,0 +1,277 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::output::view::{HubView, InstanceView, ServerView};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    /// the table with all fields
+    Wide,
+    Json,
+    Yaml,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SortBy {
+    Id,
+    Name,
+    Version,
+}
+
+/// A view that can be listed in every output format
+pub trait Listed: Serialize {
+    /// plural name, for messages
+    const KIND: &'static str;
+
+    fn id(&self) -> &str;
+    fn name(&self) -> &str;
+    fn version(&self) -> &str;
+    fn columns(wide: bool) -> Vec<&'static str>;
+    fn row(&self, wide: bool) -> Vec<String>;
+}
+
+impl Listed for HubView {
+    const KIND: &'static str = "hubs";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn columns(wide: bool) -> Vec<&'static str> {
+        let mut columns = vec!["ID", "NAME", "VERSION", "ADDRESS", "HEALTH", "RELAY STATUS"];
+        if wide {
+            columns.extend(["RELAY", "REPOSITORIES", "LABELS", "RESOURCE VERSION"]);
+        }
+        columns
+    }
+
+    fn row(&self, wide: bool) -> Vec<String> {
+        let mut row = vec![
+            self.id.to_string(),
+            self.name.to_string(),
+            self.version.to_string(),
+            format!("{}:{}", self.host, self.port),
+            self.health.to_string(),
+            self.relay_status.to_string(),
+        ];
+        if wide {
+            let relay = if self.relay_host.is_empty() { "".to_string() } else { format!("{}:{}", self.relay_host, self.relay_port) };
+            row.push(relay);
+            row.push(self.repositories.to_string());
+            row.push(labels(&self.labels));
+            row.push(self.resource_version.to_string());
+        }
+        row
+    }
+}
+
+impl Listed for ServerView {
+    const KIND: &'static str = "servers";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn columns(wide: bool) -> Vec<&'static str> {
+        let mut columns = vec!["ID", "NAME", "VERSION", "ADDRESS", "HEALTH"];
+        if wide {
+            columns.extend(["REPOSITORIES", "LABELS", "RESOURCE VERSION"]);
+        }
+        columns
+    }
+
+    fn row(&self, wide: bool) -> Vec<String> {
+        let mut row = vec![
+            self.id.to_string(),
+            self.name.to_string(),
+            self.version.to_string(),
+            format!("{}:{}", self.host, self.port),
+            self.health.to_string(),
+        ];
+        if wide {
+            row.push(self.repositories.to_string());
+            row.push(labels(&self.labels));
+            row.push(self.resource_version.to_string());
+        }
+        row
+    }
+}
+
+impl Listed for InstanceView {
+    const KIND: &'static str = "instances";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn columns(wide: bool) -> Vec<&'static str> {
+        let mut columns = vec!["ID", "TYPE", "NAME", "VERSION", "ADDRESS", "HEALTH"];
+        if wide {
+            columns.extend(["RELAY", "RELAY STATUS", "REPOSITORIES", "LABELS", "RESOURCE VERSION"]);
+        }
+        columns
+    }
+
+    fn row(&self, wide: bool) -> Vec<String> {
+        let mut row = vec![
+            self.id.to_string(),
+            self.instance_type.to_string(),
+            self.name.to_string(),
+            self.version.to_string(),
+            format!("{}:{}", self.host, self.port),
+            self.health.to_string(),
+        ];
+        if wide {
+            let relay = if self.relay_host.is_empty() { "".to_string() } else { format!("{}:{}", self.relay_host, self.relay_port) };
+            row.push(relay);
+            row.push(self.relay_status.to_string());
+            row.push(self.repositories.to_string());
+            row.push(labels(&self.labels));
+            row.push(self.resource_version.to_string());
+        }
+        row
+    }
+}
+
+/// `key=value` pairs, separated by commas like a label selector
+fn labels(labels: &BTreeMap<String, String>) -> String {
+    labels.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<_>>().join(",")
+}
+
+/// Sorts the items and prints them to stdout in the format
+pub fn print_list<T: Listed>(mut items: Vec<T>, format: OutputFormat, sort_by: SortBy) -> Result<(), Box<dyn Error>> {
+    items.sort_by(|a, b| {
+        let ordering = match sort_by {
+            SortBy::Id => Ordering::Equal,
+            SortBy::Name => a.name().cmp(b.name()),
+            SortBy::Version => compare_versions(a.version(), b.version()).then_with(|| a.name().cmp(b.name())),
+        };
+        ordering.then_with(|| a.id().cmp(b.id()))
+    });
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&items)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&items)?),
+        OutputFormat::Table | OutputFormat::Wide => {
+            if items.is_empty() {
+                // on stderr, so scripts reading the table only see the header
+                eprintln!("No {} found", T::KIND);
+            }
+            let wide = matches!(format, OutputFormat::Wide);
+            let rows: Vec<Vec<String>> = items.iter().map(|item| item.row(wide)).collect();
+            print!("{}", table(&T::columns(wide), &rows));
+        }
+    }
+    Ok(())
+}
+
+/// Left aligned columns separated by three spaces, like kubectl
+fn table(columns: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = columns.iter().map(|column| column.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut output = String::new();
+    let header: Vec<String> = columns.iter().map(|column| column.to_string()).collect();
+    for row in std::iter::once(&header).chain(rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        output.push_str(cells.join("   ").trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+/// Compares versions such as `v1.10.0` and `1.9.2` by semver precedence: numeric parts are compared as numbers,
+/// a pre-release such as `1.0.0-rc.1` ranks below its release and build metadata after `+` is ignored.
+/// Parts that are not numbers, in versions that are not semver, are compared as text
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let split = |version: &str| -> (Vec<String>, Option<Vec<String>>) {
+        let version = version.trim_start_matches('v');
+        let version = version.split_once('+').map_or(version, |(version, _build)| version);
+        let parts = |parts: &str| parts.split('.').map(|part| part.to_string()).collect::<Vec<_>>();
+        match version.split_once('-') {
+            Some((release, pre_release)) => (parts(release), Some(parts(pre_release))),
+            None => (parts(version), None),
+        }
+    };
+    let (a_release, a_pre_release) = split(a);
+    let (b_release, b_pre_release) = split(b);
+    compare_identifiers(&a_release, &b_release).then_with(|| match (a_pre_release, b_pre_release) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => compare_identifiers(&a, &b),
+    })
+}
+
+/// numeric identifiers are compared as numbers and rank below text ones, when all are equal the longest ranks highest
+fn compare_identifiers(a: &[String], b: &[String]) -> Ordering {
+    for (a, b) in a.iter().zip(b) {
+        let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => a.cmp(b),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_versions_by_semver_precedence() {
+        let ordered = [
+            "0.9", "1.0.0-alpha", "1.0.0-alpha.1", "1.0.0-alpha.beta", "1.0.0-beta", "1.0.0-beta.2", "1.0.0-beta.11",
+            "1.0.0-rc.1", "1.0.0", "v1.0.1", "1.2", "1.9.2", "v1.10.0",
+        ];
+        for (index, a) in ordered.iter().enumerate() {
+            for (other, b) in ordered.iter().enumerate() {
+                assert_eq!(compare_versions(a, b), index.cmp(&other), "{} and {}", a, b);
+            }
+        }
+        assert_eq!(compare_versions("1.0.0+build.5", "1.0.0+build.1"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.0-rc.1+build.5", "1.0.0"), Ordering::Less);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::gitstafette_discovery::{GitstafetteHub, GitstafetteInstance, GitstafetteServer, HealthStatus, InstanceHealth, RelayStatus};
+use crate::gitstafette_info::InstanceType;
+
+/// Registered instances as shown to users, with enums by name instead of number
+#[derive(Debug, Serialize)]
+pub struct HubView {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub host: String,
+    pub port: String,
+    pub repositories: String,
+    pub relay_host: String,
+    pub relay_port: String,
+    pub health: &'static str,
+    pub relay_status: &'static str,
+    pub labels: BTreeMap<String, String>,
+    pub resource_version: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServerView {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub host: String,
+    pub port: String,
+    pub repositories: String,
+    pub health: &'static str,
+    pub labels: BTreeMap<String, String>,
+    pub resource_version: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstanceView {
+    pub id: String,
+    pub instance_type: &'static str,
+    pub name: String,
+    pub version: String,
+    pub host: String,
+    pub port: String,
+    pub repositories: String,
+    pub relay_host: String,
+    pub relay_port: String,
+    pub health: &'static str,
+    pub relay_status: &'static str,
+    pub labels: BTreeMap<String, String>,
+    pub resource_version: u64,
+}
+
+fn instance_type_name(instance_type: i32) -> &'static str {
+    match InstanceType::try_from(instance_type) {
+        Ok(InstanceType::Relay) => "relay",
+        Ok(InstanceType::Server) => "server",
+        Ok(InstanceType::Client) => "client",
+        Ok(InstanceType::Hub) => "hub",
+        Ok(InstanceType::Discovery) => "discovery",
+        Ok(InstanceType::Explorer) => "explorer",
+        Err(_) => "unknown",
+    }
+}
+
+fn health_name(health: Option<&InstanceHealth>) -> &'static str {
+    match health.map(|health| HealthStatus::try_from(health.status)) {
+        Some(Ok(HealthStatus::Healthy)) => "healthy",
+        Some(Ok(HealthStatus::Unhealthy)) => "unhealthy",
+        _ => "unknown",
+    }
+}
+
+fn relay_status_name(relay_status: i32) -> &'static str {
+    match RelayStatus::try_from(relay_status) {
+        Ok(RelayStatus::Reachable) => "reachable",
+        Ok(RelayStatus::Unreachable) => "unreachable",
+        Ok(RelayStatus::Unset) => "unset",
+        _ => "unknown",
+    }
+}
+
+impl From<GitstafetteHub> for HubView {
+    fn from(hub: GitstafetteHub) -> Self {
+        HubView {
+            health: health_name(hub.health.as_ref()),
+            relay_status: relay_status_name(hub.relay_status),
+            id: hub.id,
+            name: hub.name,
+            version: hub.version,
+            host: hub.host,
+            port: hub.port,
+            repositories: hub.repositories,
+            relay_host: hub.relay_host,
+            relay_port: hub.relay_port,
+            labels: hub.labels,
+            resource_version: hub.resource_version,
+        }
+    }
+}
+
+impl From<GitstafetteServer> for ServerView {
+    fn from(server: GitstafetteServer) -> Self {
+        ServerView {
+            health: health_name(server.health.as_ref()),
+            id: server.id,
+            name: server.name,
+            version: server.version,
+            host: server.host,
+            port: server.port,
+            repositories: server.repositories,
+            labels: server.labels,
+            resource_version: server.resource_version,
+        }
+    }
+}
+
+impl From<GitstafetteInstance> for InstanceView {
+    fn from(instance: GitstafetteInstance) -> Self {
+        InstanceView {
+            instance_type: instance_type_name(instance.instance_type),
+            health: health_name(instance.health.as_ref()),
+            relay_status: relay_status_name(instance.relay_status),
+            id: instance.id,
+            name: instance.name,
+            version: instance.version,
+            host: instance.host,
+            port: instance.port,
+            repositories: instance.repositories,
+            relay_host: instance.relay_host,
+            relay_port: instance.relay_port,
+            labels: instance.labels,
+            resource_version: instance.resource_version,
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,54 @@
+use std::time::{Duration, Instant};
+
+use tokio::task::JoinSet;
+
+use crate::gitstafette_discovery::FleetInstanceInfo;
+use crate::gitstafette_info::InstanceType;
+use crate::probe::prober::{connect, get_info, ProbeError};
+use crate::store::backend::{Store, StoreResult};
+use crate::to_instance_type;
+
+/// Calls GetInfo on every instance registered in the namespace concurrently.
+/// Instances that cannot be reached within the timeout are returned with an error instead of info.
+pub async fn get_fleet_info<S: Store>(store: &S, namespace: &str, timeout: Duration) -> StoreResult<Vec<FleetInstanceInfo>> {
+  let mut requests = JoinSet::new();
+  for instance in store.get_instances(namespace, None).await? {
+    let instance_type = to_instance_type(instance.instance_type);
+    requests.spawn(get_instance_info(instance.id.clone(), instance.name.clone(), instance_type, instance.host.clone(), instance.port.clone(), timeout));
+  }
+
+  let mut instances = Vec::new();
+  while let Some(joined) = requests.join_next().await {
+    match joined {
+      Ok(instance) => instances.push(instance),
+      Err(error) => println!("Fleet info request failed: {:?}", error),
+    }
+  }
+  instances.sort_by(|a, b| a.instance_type.cmp(&b.instance_type).then_with(|| a.id.cmp(&b.id)));
+  Ok(instances)
+}
+
+async fn get_instance_info(id: String, name: String, instance_type: InstanceType, host: String, port: String, timeout: Duration) -> FleetInstanceInfo {
+  let mut instance = FleetInstanceInfo {
+    id,
+    name,
+    instance_type: instance_type.into(),
+    host,
+    port,
+    info: None,
+    error: "".to_string(),
+    latency_ms: 0,
+  };
+
+  let started = Instant::now();
+  let result = match connect(&instance.host, &instance.port, timeout).await {
+    Ok(channel) => get_info(channel).await.map_err(ProbeError::Rpc),
+    Err(error) => Err(error),
+  };
+  instance.latency_ms = started.elapsed().as_millis() as u64;
+  match result {
+    Ok(info) => instance.info = Some(info),
+    Err(error) => instance.error = error.to_string(),
+  }
+  instance
+}
@@ -0,0 +1,2 @@
+pub mod prober;
+pub mod fleet;
\ No newline at end of file
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use autometrics::autometrics;
+use opentelemetry::{global, KeyValue};
+use opentelemetry::metrics::MeterProvider;
+use tokio::net::TcpStream;
+use tokio::task::JoinSet;
+use tonic::Code;
+use tonic::transport::{Channel, Endpoint};
+use tonic_health::pb::health_client::HealthClient;
+use tonic_health::pb::health_check_response::ServingStatus;
+use tonic_health::pb::HealthCheckRequest;
+
+use crate::gitstafette_info::{GetInfoRequest, GetInfoResponse};
+use crate::gitstafette_info::info_client::InfoClient;
+use crate::store::backend::{Store, StoreResult};
+use crate::store::model::{GSFHealth, HealthStatus, InstanceType, RelayStatus};
+
+/// Hubs per relay status as of the last probe round, the gauge callback cannot wait for the Store
+type RelayCounts = Arc<Mutex<HashMap<RelayStatus, u64>>>;
+
+#[derive(Debug, Clone)]
+pub struct ProbeConfig {
+  pub interval: Duration,
+  pub timeout: Duration,
+  /// consecutive failed probes before an instance is marked unhealthy
+  pub failure_threshold: u32,
+}
+
+struct ProbeOutcome {
+  namespace: String,
+  instance_type: InstanceType,
+  id: String,
+  /// the registration that was probed, the results are dropped when it was replaced in the meantime
+  resource_version: u64,
+  previous: GSFHealth,
+  result: Result<Duration, ProbeError>,
+  /// previous and current relay status, for hubs only
+  relay_status: Option<(RelayStatus, RelayStatus)>,
+}
+
+#[derive(Debug)]
+pub enum ProbeError {
+  InvalidEndpoint(String),
+  Unreachable(tonic::transport::Error),
+  Rpc(tonic::Status),
+  NotAlive,
+  NotServing(i32),
+}
+
+impl std::fmt::Display for ProbeError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ProbeError::InvalidEndpoint(endpoint) => write!(f, "invalid endpoint: {}", endpoint),
+      ProbeError::Unreachable(error) => write!(f, "unreachable: {}", error),
+      ProbeError::Rpc(status) => write!(f, "{:?}: {}", status.code(), status.message()),
+      ProbeError::NotAlive => write!(f, "instance reports it is not alive"),
+      ProbeError::NotServing(status) => write!(f, "health check status: {:?}", ServingStatus::try_from(*status).unwrap_or(ServingStatus::Unknown)),
+    }
+  }
+}
+
+/// Periodically probes every registered instance, and the relays of the hubs, and records the result in the Store.
+pub fn start_prober<S: Store>(config: ProbeConfig, store: S) {
+  println!("Probing registered instances every {:?}", config.interval);
+  let relay_counts = RelayCounts::default();
+  register_relay_metrics(relay_counts.clone());
+  tokio::spawn(async move {
+    let mut interval = tokio::time::interval(config.interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+      interval.tick().await;
+      if let Err(error) = probe_all(&config, &store, &relay_counts).await {
+        println!("Probing failed: {}", error);
+      }
+    }
+  });
+}
+
+async fn probe_all<S: Store>(config: &ProbeConfig, store: &S, relay_counts: &RelayCounts) -> StoreResult<()> {
+  let namespaces = store.namespaces().await?;
+  let mut probes = JoinSet::new();
+  for namespace in &namespaces {
+    for instance in store.get_instances(namespace, None).await? {
+      let timeout = config.timeout;
+      let namespace = namespace.clone();
+      probes.spawn(async move {
+        if instance.instance_type == InstanceType::Hub {
+          let (result, relay_status) = tokio::join!(
+            probe(&instance.host, &instance.port, timeout),
+            probe_relay(&instance.relay_host, &instance.relay_port, timeout)
+          );
+          return ProbeOutcome {
+            namespace,
+            instance_type: instance.instance_type,
+            id: instance.id.clone(),
+            resource_version: instance.resource_version,
+            previous: instance.health.clone(),
+            result,
+            relay_status: Some((instance.relay_status, relay_status)),
+          };
+        }
+        let result = probe(&instance.host, &instance.port, timeout).await;
+        ProbeOutcome {
+          namespace,
+          instance_type: instance.instance_type,
+          id: instance.id.clone(),
+          resource_version: instance.resource_version,
+          previous: instance.health.clone(),
+          result,
+          relay_status: None,
+        }
+      });
+    }
+  }
+
+  while let Some(joined) = probes.join_next().await {
+    let Ok(outcome) = joined else {
+      continue;
+    };
+    let health = next_health(&outcome.previous, outcome.result, config.failure_threshold);
+    if health.status != outcome.previous.status {
+      println!("{:?} {} in namespace {} is now {:?} {}", outcome.instance_type, outcome.id, outcome.namespace, health.status, health.last_error);
+    }
+    if let Some((previous_relay_status, relay_status)) = outcome.relay_status {
+      if relay_status != previous_relay_status {
+        println!("Relay of hub {} in namespace {} is now {:?}", outcome.id, outcome.namespace, relay_status);
+      }
+      store.set_relay_status(&outcome.namespace, outcome.instance_type, &outcome.id, outcome.resource_version, relay_status).await?;
+    }
+    store.set_health(&outcome.namespace, outcome.instance_type, &outcome.id, outcome.resource_version, health).await?;
+  }
+
+  // the gauge counts the hubs of all namespaces
+  let mut hubs_per_status: HashMap<RelayStatus, u64> = HashMap::new();
+  for namespace in &namespaces {
+    for hub in store.get_instances(namespace, Some(InstanceType::Hub)).await? {
+      *hubs_per_status.entry(hub.relay_status).or_default() += 1;
+    }
+  }
+  *relay_counts.lock().unwrap() = hubs_per_status;
+  Ok(())
+}
+
+/// Exposes the number of hubs per relay status, so unreachable or missing relays can be alerted on
+fn register_relay_metrics(relay_counts: RelayCounts) {
+  let meter = global::meter_provider().meter("gsf-discovery");
+  let _gauge = meter
+    .u64_observable_gauge("gsf_hub_relay_status")
+    .with_description("Number of registered hubs per relay status")
+    .with_callback(move |observer| {
+      let hubs_per_status = relay_counts.lock().unwrap();
+      for status in [RelayStatus::Unknown, RelayStatus::Reachable, RelayStatus::Unreachable, RelayStatus::Unset] {
+        let count = hubs_per_status.get(&status).copied().unwrap_or_default();
+        observer.observe(count, &[KeyValue::new("status", format!("{:?}", status).to_lowercase())]);
+      }
+    })
+    .init();
+}
+
+/// A relay is unset when the hub registered no host, a placeholder such as `N/A`, or an invalid port
+fn relay_unset(relay_host: &str, relay_port: &str) -> bool {
+  let relay_host = relay_host.trim();
+  relay_host.is_empty() || relay_host.eq_ignore_ascii_case("n/a") || relay_port.trim().parse::<u16>().is_err()
+}
+
+/// Checks the relay endpoint of a hub can be connected to.
+/// The relay does not have to speak gRPC, so only a TCP connection is made.
+async fn probe_relay(relay_host: &str, relay_port: &str, timeout: Duration) -> RelayStatus {
+  if relay_unset(relay_host, relay_port) {
+    return RelayStatus::Unset;
+  }
+  let address = format!("{}:{}", relay_host.trim(), relay_port.trim());
+  match tokio::time::timeout(timeout, TcpStream::connect(address)).await {
+    Ok(Ok(_)) => RelayStatus::Reachable,
+    _ => RelayStatus::Unreachable,
+  }
+}
+
+fn next_health(previous: &GSFHealth, result: Result<Duration, ProbeError>, failure_threshold: u32) -> GSFHealth {
+  let last_checked = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_secs())
+    .unwrap_or_default();
+
+  match result {
+    Ok(latency) => GSFHealth {
+      status: HealthStatus::Healthy,
+      latency_ms: latency.as_millis() as u64,
+      last_error: previous.last_error.to_string(),
+      last_checked,
+      consecutive_failures: 0,
+    },
+    Err(error) => {
+      let consecutive_failures = previous.consecutive_failures + 1;
+      // keep the previous status until the failures pass the threshold
+      let status = if consecutive_failures >= failure_threshold {
+        HealthStatus::Unhealthy
+      } else {
+        previous.status
+      };
+      GSFHealth {
+        status,
+        latency_ms: previous.latency_ms,
+        last_error: error.to_string(),
+        last_checked,
+        consecutive_failures,
+      }
+    }
+  }
+}
+
+/// Calls the Info service of the instance, falling back to the gRPC health service
+/// for instances that do not implement Info. Returns the latency of the successful call.
+#[autometrics]
+async fn probe(host: &str, port: &str, timeout: Duration) -> Result<Duration, ProbeError> {
+  let started = Instant::now();
+  let channel = connect(host, port, timeout).await?;
+  match get_info(channel.clone()).await {
+    Ok(info) if info.alive => Ok(started.elapsed()),
+    Ok(_) => Err(ProbeError::NotAlive),
+    Err(status) if status.code() == Code::Unimplemented => check_health(channel, started).await,
+    Err(status) => Err(ProbeError::Rpc(status)),
+  }
+}
+
+/// Connects to the gRPC endpoint of a registered instance, the timeout applies to connecting and to every request
+pub async fn connect(host: &str, port: &str, timeout: Duration) -> Result<Channel, ProbeError> {
+  let address = format!("http://{}:{}", host, port);
+  let endpoint = Endpoint::from_shared(address.clone())
+    .map_err(|_| ProbeError::InvalidEndpoint(address))?
+    .connect_timeout(timeout)
+    .timeout(timeout);
+  endpoint.connect().await.map_err(ProbeError::Unreachable)
+}
+
+pub async fn get_info(channel: Channel) -> Result<GetInfoResponse, tonic::Status> {
+  let mut info_client = InfoClient::new(channel);
+  let request = GetInfoRequest {
+    client_id: "gsf-discovery".to_string(),
+    client_endpoint: "".to_string(),
+  };
+  info_client.get_info(request).await.map(|response| response.into_inner())
+}
+
+async fn check_health(channel: Channel, started: Instant) -> Result<Duration, ProbeError> {
+  let mut health_client = HealthClient::new(channel);
+  let request = HealthCheckRequest { service: "".to_string() };
+  let response = health_client.check(request).await.map_err(ProbeError::Rpc)?;
+  let status = response.get_ref().status;
+  if status == ServingStatus::Serving as i32 {
+    Ok(started.elapsed())
+  } else {
+    Err(ProbeError::NotServing(status))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const THRESHOLD: u32 = 3;
+
+  #[test]
+  fn is_healthy_after_a_successful_probe() {
+    let failing = GSFHealth { status: HealthStatus::Unhealthy, consecutive_failures: 5, last_error: "not alive".to_string(), ..GSFHealth::default() };
+    let health = next_health(&failing, Ok(Duration::from_millis(12)), THRESHOLD);
+    assert_eq!(health.status, HealthStatus::Healthy);
+    assert_eq!(health.consecutive_failures, 0);
+    assert_eq!(health.latency_ms, 12);
+    assert!(health.last_checked > 0);
+  }
+
+  #[test]
+  fn keeps_the_status_until_the_failures_reach_the_threshold() {
+    let mut health = next_health(&GSFHealth::default(), Ok(Duration::from_millis(12)), THRESHOLD);
+    for failures in 1..THRESHOLD {
+      health = next_health(&health, Err(ProbeError::NotAlive), THRESHOLD);
+      assert_eq!((health.status, health.consecutive_failures), (HealthStatus::Healthy, failures));
+      assert_eq!(health.last_error, ProbeError::NotAlive.to_string());
+      // the latency of the last successful probe
+      assert_eq!(health.latency_ms, 12);
+    }
+
+    health = next_health(&health, Err(ProbeError::NotAlive), THRESHOLD);
+    assert_eq!((health.status, health.consecutive_failures), (HealthStatus::Unhealthy, THRESHOLD));
+    health = next_health(&health, Err(ProbeError::NotAlive), THRESHOLD);
+    assert_eq!((health.status, health.consecutive_failures), (HealthStatus::Unhealthy, THRESHOLD + 1));
+  }
+
+  #[test]
+  fn stays_unknown_below_the_threshold() {
+    let health = next_health(&GSFHealth::default(), Err(ProbeError::NotAlive), THRESHOLD);
+    assert_eq!((health.status, health.consecutive_failures), (HealthStatus::Unknown, 1));
+    // with a threshold of 1, the first failure counts
+    assert_eq!(next_health(&GSFHealth::default(), Err(ProbeError::NotAlive), 1).status, HealthStatus::Unhealthy);
+  }
+}
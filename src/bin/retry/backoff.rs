@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+/// Exponential backoff with jitter, for retrying calls to the local instance and the Discovery Server, and webhook deliveries
+#[derive(Debug)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    attempt: u32,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Backoff {
+            initial,
+            max,
+            attempt: 0,
+        }
+    }
+
+    /// Returns the delay before the next attempt, which doubles with every consecutive failure up to the maximum.
+    /// The delay is randomized between half and the full value, so callers that failed together do not retry together.
+    pub fn next_delay(&mut self) -> Duration {
+        let exponent = self.attempt.min(16);
+        self.attempt = self.attempt.saturating_add(1);
+        let delay = self.initial.saturating_mul(2u32.pow(exponent)).min(self.max);
+        let half = delay / 2;
+        half + half.mul_f64(rand::random::<f64>())
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the delays of consecutive failures, which are between half and the full value of the unrandomized delay
+    fn assert_delays(backoff: &mut Backoff, expected: &[u64]) {
+        for (attempt, expected) in expected.iter().enumerate() {
+            let expected = Duration::from_millis(*expected);
+            let delay = backoff.next_delay();
+            assert!(delay >= expected / 2 && delay <= expected, "attempt {}: {:?} is not within half of {:?}", attempt, delay, expected);
+        }
+    }
+
+    #[test]
+    fn doubles_up_to_the_maximum() {
+        let mut backoff = Backoff::new(Duration::from_millis(100), Duration::from_millis(1000));
+        assert_delays(&mut backoff, &[100, 200, 400, 800, 1000, 1000]);
+    }
+
+    #[test]
+    fn starts_over_after_a_reset() {
+        let mut backoff = Backoff::new(Duration::from_millis(100), Duration::from_millis(1000));
+        assert_delays(&mut backoff, &[100, 200, 400]);
+        backoff.reset();
+        assert_delays(&mut backoff, &[100, 200]);
+    }
+
+    #[test]
+    fn does_not_overflow_after_many_failures() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(60));
+        for _ in 0..100 {
+            backoff.next_delay();
+        }
+        assert_delays(&mut backoff, &[60_000]);
+    }
+
+    #[test]
+    fn randomizes_the_delay() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(1));
+        let delays: Vec<Duration> = (0..20).map(|_| backoff.next_delay()).collect();
+        assert!(delays.iter().any(|delay| *delay != delays[0]), "20 delays without jitter: {:?}", delays);
+    }
+}
@@ -0,0 +1 @@
+pub mod backoff;
\ No newline at end of file
@@ -1,4 +1,5 @@
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tonic::{Request, Response, Status, transport::Server};
 use autometrics::{autometrics, prometheus_exporter};
 
@@ -8,28 +9,38 @@ use clap::{Parser};
 use opentelemetry::{Context, global, propagation::Extractor, trace::{Span, Tracer}};
 use opentelemetry::trace::TraceContextExt;
 
-use gitstafette_discovery::{GetHubsRequest, GetHubsResponse,RegisterHubRequest,RegisterHubResponse, RegisterServerRequest, RegisterServerResponse, GetServersRequest, GetServersResponse, GitstafetteHub, GitstafetteServer, RegisterResponse,
+use gitstafette_discovery::{GetHubsRequest, GetHubsResponse,RegisterHubRequest,RegisterHubResponse, RegisterServerRequest, RegisterServerResponse, GetServersRequest, GetServersResponse, GitstafetteHub, GitstafetteServer, RegisterResponse, InstanceHealth, GetFleetInfoRequest, GetFleetInfoResponse, DeregisterHubRequest, DeregisterHubResponse, DeregisterServerRequest, DeregisterServerResponse,
+  GitstafetteInstance, RegisterInstanceRequest, RegisterInstanceResponse, DeregisterInstanceRequest, DeregisterInstanceResponse, ListInstancesRequest, ListInstancesResponse,
+  ListNamespacesRequest, ListNamespacesResponse, NamespaceInfo, GetChangesRequest, GetChangesResponse, InstanceChange, ChangeType,
   discovery_server::{Discovery, DiscoveryServer}
 };
 
-use gitstafette_info::{GetInfoRequest, GetInfoResponse, InstanceType, ServerInfo,
+use gitstafette_info::{GetInfoRequest, GetInfoResponse, ServerInfo,
   info_server::{Info, InfoServer}
 };
 use crate::otel::tracing::create_server_span_from_context;
 
-use crate::store::inmemory::*;
+use crate::store::backend::Store;
+use crate::store::events::{EventType, StoreEvent};
+use crate::store::inmemory::InMemoryStore;
+use crate::store::model::{GSFHealth, GSFInstance, HealthStatus, InstanceType, RelayStatus};
+use crate::store::selector::LabelSelector;
+use crate::store::watch::{changes_since, wait_for_change};
+use crate::webhook::dispatcher::{start_webhook_dispatcher, WebhookConfig};
+use crate::probe::prober::{start_prober, ProbeConfig};
+use crate::probe::fleet::get_fleet_info;
+use crate::namespace::policy::{bearer_token, Access, NamespacePolicy};
+use crate::namespace::scope::{request_namespace, NamespaceError, DEFAULT_NAMESPACE};
 
 mod store;
 mod otel;
+mod webhook;
+mod retry;
+mod api;
+mod probe;
+mod namespace;
 
-// https://timvw.be/2022/04/28/notes-on-using-grpc-with-rust-and-tonic/
-#[allow(clippy::derive_partial_eq_without_eq)] // tonic don't derive Eq for generated types. We shouldn't manually change it.
-#[path = "gitstafette_discovery.rs"]
-pub mod gitstafette_discovery;
-
-#[allow(clippy::derive_partial_eq_without_eq)] // tonic don't derive Eq for generated types. We shouldn't manually change it.
-#[path = "gitstafette_info.rs"]
-pub mod gitstafette_info;
+pub use gsf_discovery::proto::{gitstafette_discovery, gitstafette_info};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -45,6 +56,46 @@ struct Cli {
   /// Gitstatfette Discovery Webserver Port
   #[arg(short, long, default_value = "8080")]
   web_port: String,
+
+  /// Webhook URL that receives a POST for every registry change (can be repeated)
+  #[arg(long = "webhook")]
+  webhooks: Vec<String>,
+
+  /// Secret used to sign webhook payloads (HMAC-SHA256), sent in the X-Gitstafette-Signature header
+  #[arg(long)]
+  webhook_secret: Option<String>,
+
+  /// Maximum number of events queued per webhook, the oldest events are dropped when full
+  #[arg(long, default_value = "1000")]
+  webhook_queue_size: usize,
+
+  /// Maximum number of retries for a failed webhook delivery
+  #[arg(long, default_value = "5")]
+  webhook_max_retries: u32,
+
+  /// Timeout in seconds for a single webhook delivery
+  #[arg(long, default_value = "5")]
+  webhook_timeout: u64,
+
+  /// Interval in seconds between health probes of the registered instances (0 disables probing)
+  #[arg(long, default_value = "30")]
+  probe_interval: u64,
+
+  /// Timeout in seconds for a single health probe (at least 1)
+  #[arg(long, default_value = "5", value_parser = clap::value_parser!(u64).range(1..))]
+  probe_timeout: u64,
+
+  /// Number of consecutive failed probes before an instance is marked unhealthy (at least 1)
+  #[arg(long, default_value = "3", value_parser = clap::value_parser!(u32).range(1..))]
+  probe_failure_threshold: u32,
+
+  /// Default timeout in milliseconds for each instance queried by GetFleetInfo
+  #[arg(long, default_value = "5000")]
+  fleet_info_timeout: u32,
+
+  /// YAML file with the bearer tokens that may read and write each namespace, all namespaces are open without it
+  #[arg(long)]
+  namespace_policy: Option<String>,
 }
 
 #[tokio::main]
@@ -57,10 +108,35 @@ pub async fn main() {
   let cli = Cli::parse();
   let address = format!("{}:{}", cli.listener_address, cli.port);
   let web_address = format!("{}:{}", cli.listener_address, cli.web_port);
-  let discovery_service = DiscoveryServer::new(DiscoveryService{store: InMemoryStore::new()});
+  let store = InMemoryStore::new();
+  let policy = match &cli.namespace_policy {
+    Some(path) => NamespacePolicy::from_file(path).unwrap_or_else(|error| {
+      eprintln!("{}", error);
+      std::process::exit(1);
+    }),
+    None => NamespacePolicy::default(),
+  };
+  let policy = Arc::new(policy);
+  if !cli.webhooks.is_empty() {
+    start_webhook_dispatcher(WebhookConfig {
+      urls: cli.webhooks.clone(),
+      secret: cli.webhook_secret.clone(),
+      queue_size: cli.webhook_queue_size,
+      max_retries: cli.webhook_max_retries,
+      timeout: std::time::Duration::from_secs(cli.webhook_timeout),
+    }, store.subscribe());
+  }
+  if cli.probe_interval > 0 {
+    start_prober(ProbeConfig {
+      interval: std::time::Duration::from_secs(cli.probe_interval),
+      timeout: std::time::Duration::from_secs(cli.probe_timeout),
+      failure_threshold: cli.probe_failure_threshold,
+    }, store.clone());
+  }
+  let discovery_service = DiscoveryServer::new(DiscoveryService{store: store.clone(), policy: policy.clone(), fleet_info_timeout: cli.fleet_info_timeout});
   let info_service = InfoServer::new(InfoService{});
   let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
-  health_reporter.set_serving::<DiscoveryServer<DiscoveryService>>().await;
+  health_reporter.set_serving::<DiscoveryServer<DiscoveryService<InMemoryStore>>>().await;
 
   // create SocketAddr from address
   let socket_address = address.parse().unwrap();
@@ -83,7 +159,9 @@ pub async fn main() {
       .route(
         "/metrics",
         get(|| async { prometheus_exporter::encode_http_response() }),
-  );
+      )
+      .route("/api/v1/events", get(api::events::events_handler::<InMemoryStore>))
+      .with_state(api::events::EventsState { store, policy });
 
   axum::Server::bind(&web_addr)
       .serve(app.into_make_service())
@@ -99,19 +177,51 @@ async fn handler() -> &'static str {
   "Hello, World!"
 }
 
-#[derive(Debug, Default)]
-pub struct DiscoveryService {
-  store: InMemoryStore
+// upper limit for the timeout a client can ask for in GetFleetInfo
+const MAX_FLEET_INFO_TIMEOUT_MS: u32 = 30_000;
+// how long blocking GetHubs and GetServers requests are held when the client does not say
+const DEFAULT_WAIT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+// upper limit for the time a client can ask to hold a blocking request
+const MAX_WAIT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+/// The Discovery gRPC service, on top of any Store backend
+#[derive(Debug)]
+pub struct DiscoveryService<S: Store> {
+  store: S,
+  policy: Arc<NamespacePolicy>,
+  fleet_info_timeout: u32,
+}
+
+impl<S: Store> DiscoveryService<S> {
+  /// The namespace of the request, if its bearer token gives the access to it
+  fn namespace<T>(&self, request: &Request<T>, field: &str, access: Access) -> Result<String, NamespaceError> {
+    let namespace = request_namespace(field, request.metadata())?;
+    let authorization = request.metadata().get("authorization").and_then(|value| value.to_str().ok());
+    self.policy.authorize(&namespace, access, bearer_token(authorization))?;
+    Ok(namespace)
+  }
 }
 
 // rpc RegisterHub(RegisterHubRequest) returns (RegisterHubResponse) {}
 // rpc RegisterServer(RegisterServerRequest) returns (RegisterServerResponse) {}
+// rpc DeregisterHub(DeregisterHubRequest) returns (DeregisterHubResponse) {}
+// rpc DeregisterServer(DeregisterServerRequest) returns (DeregisterServerResponse) {}
 
 // rpc GetHubs(GetHubsRequest) returns (GetHubsResponse) {}
 // rpc GetServers(GetServersRequest) returns (GetServersResponse) {}
 
+// rpc GetFleetInfo(GetFleetInfoRequest) returns (GetFleetInfoResponse) {}
+
+// rpc RegisterInstance(RegisterInstanceRequest) returns (RegisterInstanceResponse) {}
+// rpc DeregisterInstance(DeregisterInstanceRequest) returns (DeregisterInstanceResponse) {}
+// rpc ListInstances(ListInstancesRequest) returns (ListInstancesResponse) {}
+
+// rpc ListNamespaces(ListNamespacesRequest) returns (ListNamespacesResponse) {}
+
+// rpc GetChanges(GetChangesRequest) returns (GetChangesResponse) {}
+
 #[tonic::async_trait]
-impl Discovery for DiscoveryService {
+impl<S: Store> Discovery for DiscoveryService<S> {
   #[autometrics]
   #[tracing::instrument]
   async fn register_hub(&self, request: Request<RegisterHubRequest>) -> Result<Response<RegisterHubResponse>, Status> {
@@ -123,16 +233,12 @@ impl Discovery for DiscoveryService {
 
     cx.span().add_event("RegisterHub".to_string(), vec![]);
 
-    let response: RegisterResponse = gitstafette_discovery::RegisterResponse {
-      success: true,
-      message: "Hub registered".to_string(),
-      error: "".to_string(),
-      error_code: "".to_string(),
-    };
-
+    let namespace = self.namespace(&request, &request.get_ref().namespace, Access::Write)?;
+    let expected_version = expected_version(request.get_ref().expected_version);
     let hub = request.into_inner().hub.unwrap();
-    let hub_internal = GSFHub {
+    let hub_internal = GSFInstance {
       id: hub.id.to_string(),
+      instance_type: InstanceType::Hub,
       name: hub.name.to_string(),
       version: hub.version.to_string(),
       host: hub.host.to_string(),
@@ -140,8 +246,20 @@ impl Discovery for DiscoveryService {
       repositories: hub.repositories.to_string(),
       relay_host: hub.relay_host.to_string(),
       relay_port: hub.relay_port.to_string(),
+      health: GSFHealth::default(),
+      relay_status: RelayStatus::default(),
+      labels: hub.labels,
+      resource_version: 0,
+    };
+    let resource_version = self.store.add_instance(&namespace, hub_internal, expected_version).await?;
+
+    let response: RegisterResponse = gitstafette_discovery::RegisterResponse {
+      success: true,
+      message: "Hub registered".to_string(),
+      error: "".to_string(),
+      error_code: "".to_string(),
+      resource_version,
     };
-    self.store.add_hub(hub_internal);
 
     return Ok(Response::new(RegisterHubResponse{
       response: Some(response),
@@ -158,28 +276,94 @@ impl Discovery for DiscoveryService {
 
     cx.span().add_event("RegisterServer".to_string(), vec![]);
 
-    let response: RegisterResponse = gitstafette_discovery::RegisterResponse {
-      success: true,
-      message: "Hub registered".to_string(),
-      error: "".to_string(),
-      error_code: "".to_string(),
-    };
-
+    let namespace = self.namespace(&request, &request.get_ref().namespace, Access::Write)?;
+    let expected_version = expected_version(request.get_ref().expected_version);
     let server = request.into_inner().server.unwrap();
-    let server_internal = GSFServer {
+    let server_internal = GSFInstance {
       id: server.id.to_string(),
+      instance_type: InstanceType::Server,
       name: server.name.to_string(),
       version: server.version.to_string(),
       host: server.host.to_string(),
       port: server.port.to_string(),
       repositories: server.repositories.to_string(),
+      relay_host: "".to_string(),
+      relay_port: "".to_string(),
+      health: GSFHealth::default(),
+      relay_status: RelayStatus::default(),
+      labels: server.labels,
+      resource_version: 0,
+    };
+    let resource_version = self.store.add_instance(&namespace, server_internal, expected_version).await?;
+
+    let response: RegisterResponse = gitstafette_discovery::RegisterResponse {
+      success: true,
+      message: "Hub registered".to_string(),
+      error: "".to_string(),
+      error_code: "".to_string(),
+      resource_version,
     };
-    self.store.add_server(server_internal);
     return Ok(Response::new(RegisterServerResponse{
       response: Some(response),
     }));
   }
 
+  #[autometrics]
+  #[tracing::instrument]
+  async fn deregister_hub(&self, request: Request<DeregisterHubRequest>) -> Result<Response<DeregisterHubResponse>, Status> {
+    println!("Got a request: {:?}", request);
+
+    let parent_cx = global::get_text_map_propagator(|prop| prop.extract(&MetadataMap(request.metadata())));
+    let span = create_server_span_from_context("GSF-Discovery/server".to_string(), "deregister_hub".to_string(), parent_cx);
+    let cx = Context::current_with_value(span);
+
+    cx.span().add_event("DeregisterHub".to_string(), vec![]);
+
+    let namespace = self.namespace(&request, &request.get_ref().namespace, Access::Write)?;
+    let id = request.into_inner().id;
+    if self.store.remove_instance(&namespace, InstanceType::Hub, &id).await?.is_none() {
+      return Err(Status::not_found(format!("Hub {} is not registered", id)));
+    }
+
+    return Ok(Response::new(DeregisterHubResponse{
+      response: Some(gitstafette_discovery::RegisterResponse {
+        success: true,
+        message: "Hub deregistered".to_string(),
+        error: "".to_string(),
+        error_code: "".to_string(),
+        resource_version: 0,
+      }),
+    }));
+  }
+
+  #[autometrics]
+  #[tracing::instrument]
+  async fn deregister_server(&self, request: Request<DeregisterServerRequest>) -> Result<Response<DeregisterServerResponse>, Status> {
+    println!("Got a request: {:?}", request);
+
+    let parent_cx = global::get_text_map_propagator(|prop| prop.extract(&MetadataMap(request.metadata())));
+    let span = create_server_span_from_context("GSF-Discovery/server".to_string(), "deregister_server".to_string(), parent_cx);
+    let cx = Context::current_with_value(span);
+
+    cx.span().add_event("DeregisterServer".to_string(), vec![]);
+
+    let namespace = self.namespace(&request, &request.get_ref().namespace, Access::Write)?;
+    let id = request.into_inner().id;
+    if self.store.remove_instance(&namespace, InstanceType::Server, &id).await?.is_none() {
+      return Err(Status::not_found(format!("Server {} is not registered", id)));
+    }
+
+    return Ok(Response::new(DeregisterServerResponse{
+      response: Some(gitstafette_discovery::RegisterResponse {
+        success: true,
+        message: "Server deregistered".to_string(),
+        error: "".to_string(),
+        error_code: "".to_string(),
+        resource_version: 0,
+      }),
+    }));
+  }
+
   #[autometrics]
   #[tracing::instrument]
   async fn get_hubs(&self, request: Request<GetHubsRequest>) -> Result<Response<GetHubsResponse>, Status> {
@@ -191,8 +375,21 @@ impl Discovery for DiscoveryService {
 
     cx.span().add_event("GetHubs".to_string(), vec![]);
 
+    let namespace = self.namespace(&request, &request.get_ref().namespace, Access::Read)?;
+    let include_unhealthy = request.get_ref().include_unhealthy;
+    let selector = LabelSelector::parse(&request.get_ref().label_selector).map_err(Status::invalid_argument)?;
+    let index = match request.get_ref().wait_index {
+      0 => self.store.revision().await?,
+      wait_index => wait_for_change(&self.store, &namespace, InstanceType::Hub, wait_index, wait_timeout(request.get_ref().wait_timeout_ms)).await?,
+    };
     let mut hubs: Vec<GitstafetteHub> = Vec::new();
-    for internal_hub in self.store.get_hubs() {
+    for internal_hub in self.store.get_instances(&namespace, Some(InstanceType::Hub)).await? {
+      if !include_unhealthy && internal_hub.health.status == HealthStatus::Unhealthy {
+        continue;
+      }
+      if !selector.matches(&internal_hub.labels) {
+        continue;
+      }
       let hub = GitstafetteHub {
         id: internal_hub.id.to_string(),
         name: internal_hub.name.to_string(),
@@ -202,12 +399,17 @@ impl Discovery for DiscoveryService {
         repositories: internal_hub.repositories.to_string(),
         relay_host: internal_hub.relay_host.to_string(),
         relay_port: internal_hub.relay_port.to_string(),
+        health: Some(to_instance_health(&internal_hub.health)),
+        relay_status: to_relay_status(internal_hub.relay_status).into(),
+        labels: internal_hub.labels.clone(),
+        resource_version: internal_hub.resource_version,
       };
       hubs.push(hub);
     }
 
     return Ok(Response::new(GetHubsResponse {
       hubs,
+      index,
     }));
   }
 
@@ -222,8 +424,21 @@ impl Discovery for DiscoveryService {
 
     cx.span().add_event("GetServers".to_string(), vec![]);
 
+    let namespace = self.namespace(&request, &request.get_ref().namespace, Access::Read)?;
+    let include_unhealthy = request.get_ref().include_unhealthy;
+    let selector = LabelSelector::parse(&request.get_ref().label_selector).map_err(Status::invalid_argument)?;
+    let index = match request.get_ref().wait_index {
+      0 => self.store.revision().await?,
+      wait_index => wait_for_change(&self.store, &namespace, InstanceType::Server, wait_index, wait_timeout(request.get_ref().wait_timeout_ms)).await?,
+    };
     let mut servers: Vec<GitstafetteServer> = Vec::new();
-    for internal_server in self.store.get_servers() {
+    for internal_server in self.store.get_instances(&namespace, Some(InstanceType::Server)).await? {
+      if !include_unhealthy && internal_server.health.status == HealthStatus::Unhealthy {
+        continue;
+      }
+      if !selector.matches(&internal_server.labels) {
+        continue;
+      }
       let server = GitstafetteServer {
         id: internal_server.id.to_string(),
         name: internal_server.name.to_string(),
@@ -231,16 +446,312 @@ impl Discovery for DiscoveryService {
         host: internal_server.host.to_string(),
         port: internal_server.port.to_string(),
         repositories: internal_server.repositories.to_string(),
+        health: Some(to_instance_health(&internal_server.health)),
+        labels: internal_server.labels.clone(),
+        resource_version: internal_server.resource_version,
       };
       servers.push(server);
     }
 
     return Ok(Response::new(GetServersResponse {
       servers,
+      index,
+    }));
+  }
+
+  #[autometrics]
+  #[tracing::instrument]
+  async fn get_fleet_info(&self, request: Request<GetFleetInfoRequest>) -> Result<Response<GetFleetInfoResponse>, Status> {
+    println!("Got a request: {:?}", request);
+
+    let parent_cx = global::get_text_map_propagator(|prop| prop.extract(&MetadataMap(request.metadata())));
+    let span = create_server_span_from_context("GSF-Discovery/server".to_string(), "get_fleet_info".to_string(), parent_cx);
+    let cx = Context::current_with_value(span);
+
+    cx.span().add_event("GetFleetInfo".to_string(), vec![]);
+
+    let namespace = self.namespace(&request, &request.get_ref().namespace, Access::Read)?;
+    let mut timeout_ms = request.get_ref().timeout_ms;
+    if timeout_ms == 0 {
+      timeout_ms = self.fleet_info_timeout;
+    }
+    let timeout = std::time::Duration::from_millis(timeout_ms.min(MAX_FLEET_INFO_TIMEOUT_MS) as u64);
+    let instances = get_fleet_info(&self.store, &namespace, timeout).await?;
+
+    return Ok(Response::new(GetFleetInfoResponse {
+      instances,
+    }));
+  }
+
+  #[autometrics]
+  #[tracing::instrument]
+  async fn register_instance(&self, request: Request<RegisterInstanceRequest>) -> Result<Response<RegisterInstanceResponse>, Status> {
+    println!("Got a request: {:?}", request);
+
+    let parent_cx = global::get_text_map_propagator(|prop| prop.extract(&MetadataMap(request.metadata())));
+    let span = create_server_span_from_context("GSF-Discovery/server".to_string(), "register_instance".to_string(), parent_cx);
+    let cx = Context::current_with_value(span);
+
+    cx.span().add_event("RegisterInstance".to_string(), vec![]);
+
+    let namespace = self.namespace(&request, &request.get_ref().namespace, Access::Write)?;
+    let expected_version = expected_version(request.get_ref().expected_version);
+    let Some(instance) = request.into_inner().instance else {
+      return Err(Status::invalid_argument("instance is required"));
+    };
+    let instance_type = from_instance_type(instance.instance_type).ok_or_else(|| unknown_instance_type(instance.instance_type))?;
+    let instance_internal = GSFInstance {
+      id: instance.id.to_string(),
+      instance_type,
+      name: instance.name.to_string(),
+      version: instance.version.to_string(),
+      host: instance.host.to_string(),
+      port: instance.port.to_string(),
+      repositories: instance.repositories.to_string(),
+      relay_host: instance.relay_host.to_string(),
+      relay_port: instance.relay_port.to_string(),
+      health: GSFHealth::default(),
+      relay_status: RelayStatus::default(),
+      labels: instance.labels,
+      resource_version: 0,
+    };
+    let resource_version = self.store.add_instance(&namespace, instance_internal, expected_version).await?;
+
+    return Ok(Response::new(RegisterInstanceResponse{
+      response: Some(gitstafette_discovery::RegisterResponse {
+        success: true,
+        message: format!("Instance of type {} registered", instance_type.name()),
+        error: "".to_string(),
+        error_code: "".to_string(),
+        resource_version,
+      }),
+    }));
+  }
+
+  #[autometrics]
+  #[tracing::instrument]
+  async fn deregister_instance(&self, request: Request<DeregisterInstanceRequest>) -> Result<Response<DeregisterInstanceResponse>, Status> {
+    println!("Got a request: {:?}", request);
+
+    let parent_cx = global::get_text_map_propagator(|prop| prop.extract(&MetadataMap(request.metadata())));
+    let span = create_server_span_from_context("GSF-Discovery/server".to_string(), "deregister_instance".to_string(), parent_cx);
+    let cx = Context::current_with_value(span);
+
+    cx.span().add_event("DeregisterInstance".to_string(), vec![]);
+
+    let namespace = self.namespace(&request, &request.get_ref().namespace, Access::Write)?;
+    let request = request.into_inner();
+    let instance_type = from_instance_type(request.instance_type).ok_or_else(|| unknown_instance_type(request.instance_type))?;
+    if self.store.remove_instance(&namespace, instance_type, &request.id).await?.is_none() {
+      return Err(Status::not_found(format!("{} {} is not registered", instance_type.name(), request.id)));
+    }
+
+    return Ok(Response::new(DeregisterInstanceResponse{
+      response: Some(gitstafette_discovery::RegisterResponse {
+        success: true,
+        message: format!("Instance of type {} deregistered", instance_type.name()),
+        error: "".to_string(),
+        error_code: "".to_string(),
+        resource_version: 0,
+      }),
+    }));
+  }
+
+  #[autometrics]
+  #[tracing::instrument]
+  async fn list_instances(&self, request: Request<ListInstancesRequest>) -> Result<Response<ListInstancesResponse>, Status> {
+    println!("Got a request: {:?}", request);
+
+    let parent_cx = global::get_text_map_propagator(|prop| prop.extract(&MetadataMap(request.metadata())));
+    let span = create_server_span_from_context("GSF-Discovery/server".to_string(), "list_instances".to_string(), parent_cx);
+    let cx = Context::current_with_value(span);
+
+    cx.span().add_event("ListInstances".to_string(), vec![]);
+
+    let namespace = self.namespace(&request, &request.get_ref().namespace, Access::Read)?;
+    let request = request.into_inner();
+    let mut instance_types = Vec::new();
+    for instance_type in &request.instance_types {
+      instance_types.push(from_instance_type(*instance_type).ok_or_else(|| unknown_instance_type(*instance_type))?);
+    }
+    let selector = LabelSelector::parse(&request.label_selector).map_err(Status::invalid_argument)?;
+    let mut instances: Vec<GitstafetteInstance> = Vec::new();
+    for internal_instance in self.store.get_instances(&namespace, None).await? {
+      if !instance_types.is_empty() && !instance_types.contains(&internal_instance.instance_type) {
+        continue;
+      }
+      if !request.include_unhealthy && internal_instance.health.status == HealthStatus::Unhealthy {
+        continue;
+      }
+      if !selector.matches(&internal_instance.labels) {
+        continue;
+      }
+      instances.push(to_gitstafette_instance(&internal_instance));
+    }
+    instances.sort_by(|a, b| a.instance_type.cmp(&b.instance_type).then_with(|| a.id.cmp(&b.id)));
+
+    return Ok(Response::new(ListInstancesResponse {
+      instances,
+    }));
+  }
+
+  #[autometrics]
+  #[tracing::instrument]
+  async fn list_namespaces(&self, request: Request<ListNamespacesRequest>) -> Result<Response<ListNamespacesResponse>, Status> {
+    println!("Got a request: {:?}", request);
+
+    let parent_cx = global::get_text_map_propagator(|prop| prop.extract(&MetadataMap(request.metadata())));
+    let span = create_server_span_from_context("GSF-Discovery/server".to_string(), "list_namespaces".to_string(), parent_cx);
+    let cx = Context::current_with_value(span);
+
+    cx.span().add_event("ListNamespaces".to_string(), vec![]);
+
+    // the namespaces with instances, and those with their own rules even while they are empty
+    let mut names = self.store.namespaces().await?;
+    names.extend(self.policy.namespaces());
+    names.push(DEFAULT_NAMESPACE.to_string());
+    names.sort();
+    names.dedup();
+
+    let token = bearer_token(request.metadata().get("authorization").and_then(|value| value.to_str().ok()));
+    let mut namespaces = Vec::new();
+    for name in names {
+      if self.policy.authorize(&name, Access::Read, token).is_err() {
+        continue;
+      }
+      let instances = self.store.get_instances(&name, None).await?.len() as u32;
+      namespaces.push(NamespaceInfo { name, instances });
+    }
+
+    return Ok(Response::new(ListNamespacesResponse {
+      namespaces,
+    }));
+  }
+  #[autometrics]
+  #[tracing::instrument]
+  async fn get_changes(&self, request: Request<GetChangesRequest>) -> Result<Response<GetChangesResponse>, Status> {
+    println!("Got a request: {:?}", request);
+
+    let parent_cx = global::get_text_map_propagator(|prop| prop.extract(&MetadataMap(request.metadata())));
+    let span = create_server_span_from_context("GSF-Discovery/server".to_string(), "get_changes".to_string(), parent_cx);
+    let cx = Context::current_with_value(span);
+
+    cx.span().add_event("GetChanges".to_string(), vec![]);
+
+    let namespace = self.namespace(&request, &request.get_ref().namespace, Access::Read)?;
+    let (revision, events) = changes_since(&self.store, &namespace, request.get_ref().since_revision).await?;
+    let Some(events) = events else {
+      // the client lists everything again, and asks for the changes since the revision before that listing
+      return Ok(Response::new(GetChangesResponse {
+        revision,
+        reset: true,
+        changes: vec![],
+      }));
+    };
+
+    return Ok(Response::new(GetChangesResponse {
+      revision,
+      reset: false,
+      changes: events.iter().filter_map(to_instance_change).collect(),
     }));
   }
+
+}
+
+fn to_instance_health(health: &GSFHealth) -> InstanceHealth {
+  let status = match health.status {
+    HealthStatus::Unknown => gitstafette_discovery::HealthStatus::Unknown,
+    HealthStatus::Healthy => gitstafette_discovery::HealthStatus::Healthy,
+    HealthStatus::Unhealthy => gitstafette_discovery::HealthStatus::Unhealthy,
+  };
+  InstanceHealth {
+    status: status.into(),
+    latency_ms: health.latency_ms,
+    last_error: health.last_error.to_string(),
+    last_checked: health.last_checked,
+  }
+}
+
+fn to_relay_status(relay_status: RelayStatus) -> gitstafette_discovery::RelayStatus {
+  match relay_status {
+    RelayStatus::Unknown => gitstafette_discovery::RelayStatus::Unknown,
+    RelayStatus::Reachable => gitstafette_discovery::RelayStatus::Reachable,
+    RelayStatus::Unreachable => gitstafette_discovery::RelayStatus::Unreachable,
+    RelayStatus::Unset => gitstafette_discovery::RelayStatus::Unset,
+  }
+}
+
+fn to_gitstafette_instance(instance: &GSFInstance) -> GitstafetteInstance {
+  GitstafetteInstance {
+    id: instance.id.to_string(),
+    instance_type: to_instance_type(instance.instance_type).into(),
+    name: instance.name.to_string(),
+    version: instance.version.to_string(),
+    host: instance.host.to_string(),
+    port: instance.port.to_string(),
+    repositories: instance.repositories.to_string(),
+    relay_host: instance.relay_host.to_string(),
+    relay_port: instance.relay_port.to_string(),
+    health: Some(to_instance_health(&instance.health)),
+    relay_status: to_relay_status(instance.relay_status).into(),
+    labels: instance.labels.clone(),
+    resource_version: instance.resource_version,
+  }
+}
+
+fn to_instance_change(event: &StoreEvent) -> Option<InstanceChange> {
+  let change_type = match event.event_type {
+    EventType::Added => ChangeType::Added,
+    EventType::Updated => ChangeType::Updated,
+    EventType::Removed => ChangeType::Removed,
+  };
+  let instance = event.after.as_ref().or(event.before.as_ref())?;
+  Some(InstanceChange {
+    change_type: change_type.into(),
+    revision: event.revision,
+    instance: Some(to_gitstafette_instance(instance)),
+  })
+}
+
+/// how long a blocking GetHubs or GetServers is held
+fn wait_timeout(wait_timeout_ms: u32) -> std::time::Duration {
+  match wait_timeout_ms {
+    0 => DEFAULT_WAIT_TIMEOUT,
+    wait_timeout_ms => std::time::Duration::from_millis(wait_timeout_ms as u64).min(MAX_WAIT_TIMEOUT),
+  }
 }
 
+/// the expected resource version of a registration, 0 registers unconditionally
+fn expected_version(expected_version: u64) -> Option<u64> {
+  (expected_version != 0).then_some(expected_version)
+}
+
+pub fn to_instance_type(instance_type: InstanceType) -> gitstafette_info::InstanceType {
+  match instance_type {
+    InstanceType::Relay => gitstafette_info::InstanceType::Relay,
+    InstanceType::Server => gitstafette_info::InstanceType::Server,
+    InstanceType::Client => gitstafette_info::InstanceType::Client,
+    InstanceType::Hub => gitstafette_info::InstanceType::Hub,
+    InstanceType::Discovery => gitstafette_info::InstanceType::Discovery,
+    InstanceType::Explorer => gitstafette_info::InstanceType::Explorer,
+  }
+}
+
+fn unknown_instance_type(instance_type: i32) -> Status {
+  Status::invalid_argument(format!("unknown instance type {}", instance_type))
+}
+
+fn from_instance_type(instance_type: i32) -> Option<InstanceType> {
+  let instance_type = gitstafette_info::InstanceType::try_from(instance_type).ok()?;
+  Some(match instance_type {
+    gitstafette_info::InstanceType::Relay => InstanceType::Relay,
+    gitstafette_info::InstanceType::Server => InstanceType::Server,
+    gitstafette_info::InstanceType::Client => InstanceType::Client,
+    gitstafette_info::InstanceType::Hub => InstanceType::Hub,
+    gitstafette_info::InstanceType::Discovery => InstanceType::Discovery,
+    gitstafette_info::InstanceType::Explorer => InstanceType::Explorer,
+  })
+}
 
 #[derive(Debug, Default)]
 pub struct InfoService {
@@ -275,7 +786,7 @@ impl Info for InfoService {
 
       let response = GetInfoResponse {
         alive: true,
-        instance_type: InstanceType::Discovery.into(),
+        instance_type: gitstafette_info::InstanceType::Discovery.into(),
         version: "0.1.0".to_string(),
         name: "Gitstafette Discovery".to_string(),
         server: Some(server_info),
@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::net::IpAddr;
+
+/// Where remote consumers should reach a local instance, when it differs from what its Info service reports
+#[derive(Debug, Clone, Default)]
+pub struct Advertise {
+    /// always replaces the reported host
+    pub host: Option<String>,
+    /// always replaces the reported port
+    pub port: Option<String>,
+    /// replaces a reported host that is not reachable from other machines, such as `localhost`
+    pub local_host_replacement: Option<String>,
+}
+
+/// Environment variables set through the Kubernetes downward API, see
+/// https://kubernetes.io/docs/concepts/workloads/pods/downward-api/
+#[derive(Debug, Clone, Default)]
+pub struct DownwardApi {
+    pub pod_ip: Option<String>,
+    pub pod_name: Option<String>,
+    pub pod_namespace: Option<String>,
+    pub node_name: Option<String>,
+}
+
+impl DownwardApi {
+    pub fn from_env() -> Self {
+        let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+        DownwardApi {
+            pod_ip: var("POD_IP"),
+            pod_name: var("POD_NAME"),
+            pod_namespace: var("POD_NAMESPACE"),
+            node_name: var("NODE_NAME"),
+        }
+    }
+
+    /// `namespace` and `node` labels, for the ones that are set
+    pub fn labels(&self) -> BTreeMap<String, String> {
+        let mut labels = BTreeMap::new();
+        if let Some(namespace) = &self.pod_namespace {
+            labels.insert("namespace".to_string(), namespace.to_string());
+        }
+        if let Some(node_name) = &self.node_name {
+            labels.insert("node".to_string(), node_name.to_string());
+        }
+        labels
+    }
+
+    /// `namespace/pod`, unique within the cluster, for instances that have no id configured
+    pub fn instance_id(&self) -> Option<String> {
+        let pod_name = self.pod_name.as_ref()?;
+        match &self.pod_namespace {
+            Some(namespace) => Some(format!("{}/{}", namespace, pod_name)),
+            None => Some(pod_name.to_string()),
+        }
+    }
+}
+
+impl Advertise {
+    pub fn new(host: Option<String>, port: Option<String>, downward_api: Option<&DownwardApi>) -> Self {
+        Advertise {
+            host,
+            port,
+            local_host_replacement: downward_api.and_then(|downward_api| downward_api.pod_ip.clone()),
+        }
+    }
+
+    pub fn host(&self, reported: &str) -> String {
+        if let Some(host) = &self.host {
+            return host.to_string();
+        }
+        match &self.local_host_replacement {
+            Some(replacement) if is_local(reported) => replacement.to_string(),
+            _ => reported.to_string(),
+        }
+    }
+
+    pub fn port(&self, reported: &str) -> String {
+        self.port.clone().unwrap_or_else(|| reported.to_string())
+    }
+}
+
+/// hosts that only resolve to the machine, or the pod, itself
+fn is_local(host: &str) -> bool {
+    if host.is_empty() || host.eq_ignore_ascii_case("localhost") {
+        return true;
+    }
+    match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+        Ok(ip) => ip.is_loopback() || ip.is_unspecified(),
+        Err(_) => false,
+    }
+}
\ No newline at end of file
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use gsf_discovery::client::propagation::MetadataMap;
+use opentelemetry::{global, Context};
+use serde::Deserialize;
+use tonic::transport::{Channel, Endpoint};
+use tonic_health::pb::health_check_response::ServingStatus;
+use tonic_health::pb::health_client::HealthClient;
+use tonic_health::pb::HealthCheckRequest;
+
+use crate::gitstafette_info::{info_client::InfoClient, GetInfoRequest, GetInfoResponse, InstanceType, ServerInfo};
+use crate::sidecar::target::InfoTarget;
+
+// the gRPC clients time out through the backoff of the loop, HTTP requests need their own
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How the sidecar learns about a local instance
+#[derive(Debug, Clone, Default)]
+pub enum InfoSource {
+    /// the gRPC Info service
+    #[default]
+    Grpc,
+    /// an HTTP endpoint that returns the GetInfoResponse as JSON
+    Http { path: String },
+    /// the gRPC health service, the instance is described by static metadata
+    Health(StaticInfo),
+}
+
+/// Describes an instance that only implements the gRPC health service
+#[derive(Debug, Clone)]
+pub struct StaticInfo {
+    pub instance_type: InstanceType,
+    /// defaults to the name of the target
+    pub name: Option<String>,
+    pub version: String,
+    pub repositories: Option<String>,
+}
+
+impl Default for StaticInfo {
+    fn default() -> Self {
+        StaticInfo {
+            instance_type: InstanceType::Server,
+            name: None,
+            version: "unknown".to_string(),
+            repositories: None,
+        }
+    }
+}
+
+/// A client for the local instance, for whichever source the target uses
+pub enum LocalInfoClient {
+    Grpc(InfoClient<Channel>),
+    Http { client: reqwest::Client, url: String },
+    Health { client: HealthClient<Channel>, info: Box<GetInfoResponse> },
+}
+
+impl LocalInfoClient {
+    /// connects lazily, so creating a client never fails
+    pub fn new(target: &InfoTarget, endpoint: &Endpoint) -> Self {
+        match &target.source {
+            InfoSource::Grpc => LocalInfoClient::Grpc(InfoClient::new(endpoint.connect_lazy())),
+            InfoSource::Http { path } => LocalInfoClient::Http {
+                client: reqwest::Client::builder()
+                    .timeout(HTTP_TIMEOUT)
+                    .build()
+                    .unwrap_or_default(),
+                url: format!("{}{}", target.address(), path),
+            },
+            InfoSource::Health(static_info) => LocalInfoClient::Health {
+                client: HealthClient::new(endpoint.connect_lazy()),
+                info: Box::new(GetInfoResponse {
+                    alive: false,
+                    instance_type: static_info.instance_type.into(),
+                    version: static_info.version.to_string(),
+                    name: static_info.name.clone().unwrap_or_else(|| target.name()),
+                    server: Some(ServerInfo {
+                        hostname: target.host.to_string(),
+                        ip: "".to_string(),
+                        port: target.port.to_string(),
+                        protocol: target.protocol.to_string(),
+                        repositories: static_info.repositories.clone(),
+                    }),
+                    relay: None,
+                }),
+            },
+        }
+    }
+
+    pub async fn get_info(&mut self, cx: &Context) -> Result<GetInfoResponse, tonic::Status> {
+        match self {
+            LocalInfoClient::Grpc(client) => {
+                let info_request = GetInfoRequest { client_id: "myself".to_string(), client_endpoint: "127.0.0.1:50051".to_string() };
+                let mut request = tonic::Request::new(info_request);
+                global::get_text_map_propagator(|propagator| {
+                    propagator.inject_context(cx, &mut MetadataMap(request.metadata_mut()))
+                });
+                client.get_info(request).await.map(|response| response.into_inner())
+            }
+            LocalInfoClient::Http { client, url } => get_json_info(client, url, cx).await,
+            LocalInfoClient::Health { client, info } => {
+                let mut request = tonic::Request::new(HealthCheckRequest { service: "".to_string() });
+                global::get_text_map_propagator(|propagator| {
+                    propagator.inject_context(cx, &mut MetadataMap(request.metadata_mut()))
+                });
+                let response = client.check(request).await?;
+                Ok(GetInfoResponse {
+                    alive: response.get_ref().status == ServingStatus::Serving as i32,
+                    ..*info.clone()
+                })
+            }
+        }
+    }
+}
+
+/// GetInfoResponse in its proto3 JSON mapping, snake_case field names are accepted as well
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonInfo {
+    #[serde(default)]
+    alive: bool,
+    #[serde(default, alias = "instance_type")]
+    instance_type: Option<serde_json::Value>,
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    server: Option<JsonServerInfo>,
+    #[serde(default)]
+    relay: Option<JsonServerInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonServerInfo {
+    #[serde(default)]
+    hostname: String,
+    #[serde(default)]
+    ip: String,
+    /// a string in the proto, but often written as number
+    #[serde(default)]
+    port: Option<serde_json::Value>,
+    #[serde(default)]
+    protocol: String,
+    #[serde(default)]
+    repositories: Option<String>,
+}
+
+async fn get_json_info(client: &reqwest::Client, url: &str, cx: &Context) -> Result<GetInfoResponse, tonic::Status> {
+    let mut headers = HashMap::new();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(cx, &mut headers));
+    let mut request = client.get(url).header(reqwest::header::ACCEPT, "application/json");
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|error| tonic::Status::unavailable(error.to_string()))?;
+    if !response.status().is_success() {
+        return Err(tonic::Status::unavailable(format!("{} returned {}", url, response.status())));
+    }
+    let body = response
+        .bytes()
+        .await
+        .map_err(|error| tonic::Status::unavailable(error.to_string()))?;
+    serde_json::from_slice(&body)
+        .map_err(|error| error.to_string())
+        .and_then(to_info_response)
+        .map_err(|error| tonic::Status::internal(format!("invalid info from {}: {}", url, error)))
+}
+
+/// The instance type is required, although proto3 JSON may omit its default RELAY,
+/// so that a component that does not report its type is not registered as a relay
+fn to_info_response(info: JsonInfo) -> Result<GetInfoResponse, String> {
+    let instance_type = match &info.instance_type {
+        None | Some(serde_json::Value::Null) => return Err("missing instanceType".to_string()),
+        Some(serde_json::Value::Number(number)) => number
+            .as_i64()
+            .and_then(|number| InstanceType::try_from(number as i32).ok())
+            .ok_or_else(|| format!("unknown instance type {}", number))?,
+        Some(serde_json::Value::String(name)) => InstanceType::from_str_name(&name.to_uppercase())
+            .ok_or_else(|| format!("unknown instance type {}", name))?,
+        Some(other) => return Err(format!("unknown instance type {}", other)),
+    };
+    Ok(GetInfoResponse {
+        alive: info.alive,
+        instance_type: instance_type.into(),
+        version: info.version,
+        name: info.name,
+        server: info.server.map(to_server_info),
+        relay: info.relay.map(to_server_info),
+    })
+}
+
+fn to_server_info(info: JsonServerInfo) -> ServerInfo {
+    let port = match info.port {
+        Some(serde_json::Value::String(port)) => port,
+        Some(serde_json::Value::Number(port)) => port.to_string(),
+        _ => "".to_string(),
+    };
+    ServerInfo {
+        hostname: info.hostname,
+        ip: info.ip,
+        port,
+        protocol: info.protocol,
+        repositories: info.repositories,
+    }
+}
\ No newline at end of file
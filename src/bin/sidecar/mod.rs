@@ -0,0 +1,7 @@
+pub mod advertise;
+pub mod info;
+pub mod registration;
+pub mod render;
+pub mod status;
+pub mod supervisor;
+pub mod target;
\ No newline at end of file
@@ -0,0 +1,226 @@
+use std::error::Error;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use autometrics::autometrics;
+use opentelemetry::metrics::{Counter, MeterProvider};
+use gsf_discovery::{ClientError, DiscoveryClient};
+use opentelemetry::{global, trace::TraceContextExt, Context, KeyValue};
+use tonic::transport::Endpoint;
+
+use crate::gitstafette_discovery::{GitstafetteInstance, RelayStatus};
+use crate::gitstafette_info::{GetInfoResponse, InstanceType};
+use crate::otel;
+use crate::retry::backoff::Backoff;
+use crate::sidecar::info::LocalInfoClient;
+use crate::sidecar::status::{RegisteredAs, SyncStatus};
+use crate::sidecar::target::InfoTarget;
+
+// first delay after a failed attempt, doubling with every consecutive failure
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+// consecutive attempts the local instance has to be unavailable before it is deregistered
+const DEREGISTER_AFTER_FAILURES: u32 = 3;
+
+fn sync_attempts() -> &'static Counter<u64> {
+    static SYNC_ATTEMPTS: OnceLock<Counter<u64>> = OnceLock::new();
+    SYNC_ATTEMPTS.get_or_init(|| {
+        global::meter_provider()
+            .meter("gsf-discovery")
+            .u64_counter("gsf_sidecar_sync_attempts")
+            .with_description("Info registration attempts per target and result")
+            .init()
+    })
+}
+
+#[derive(Debug)]
+enum SyncError {
+    /// the local Info service could not be queried
+    Info(tonic::Status),
+    /// the local instance reports it is not alive
+    NotAlive,
+    /// the Discovery Server did not accept the registration
+    Registration(ClientError),
+}
+
+impl std::fmt::Display for SyncError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncError::Info(status) => write!(f, "local Info service failed: {:?} {}", status.code(), status.message()),
+            SyncError::NotAlive => write!(f, "local instance is not alive"),
+            SyncError::Registration(error) => write!(f, "registration failed: {}", error),
+        }
+    }
+}
+
+impl SyncError {
+    /// result label of the sync attempts metric
+    fn result(&self) -> &'static str {
+        match self {
+            SyncError::Info(_) => "info_failed",
+            SyncError::NotAlive => "not_alive",
+            SyncError::Registration(_) => "registration_failed",
+        }
+    }
+}
+
+/// Registers the local instance every interval while it is alive, backing off while it or the Discovery Server is unavailable.
+/// The instance is deregistered once it stays unavailable for several attempts, and registered again when it recovers.
+/// Only returns when the address of the local instance is invalid.
+#[tracing::instrument]
+pub async fn sync_local_status_to_discovery_server(discovery_client: &DiscoveryClient, target: &InfoTarget, interval: Duration, max_backoff: Duration, status: &SyncStatus) -> Result<(), Box<dyn Error>> {
+    let server = target.address();
+    let info_endpoint = Endpoint::from_shared(server.clone())?;
+    println!("info client for {} connecting to: {}", target.name(), server);
+
+    // the info client is connected lazily, after a failed call it is dropped so the next attempt reconnects,
+    // the discovery client fails over to another Discovery Server by itself
+    let mut info_client: Option<LocalInfoClient> = None;
+    let mut backoff = Backoff::new(INITIAL_BACKOFF, max_backoff);
+    let mut unavailable_attempts = 0;
+
+    loop {
+        let span = otel::tracing::create_client_span( "GSF-Discovery/client".to_string(), "sync_local_status_to_discovery_server".to_string());
+        let cx = Context::current_with_span(span);
+
+        let info = info_client.get_or_insert_with(|| LocalInfoClient::new(target, &info_endpoint));
+
+        let target_label = KeyValue::new("target", target.name());
+        let delay = match sync_local_status(info, discovery_client, target, status, &cx).await {
+            Ok(()) => {
+                sync_attempts().add(1, &[target_label, KeyValue::new("result", "ok")]);
+                unavailable_attempts = 0;
+                backoff.reset();
+                interval
+            }
+            Err(error) => {
+                sync_attempts().add(1, &[target_label, KeyValue::new("result", error.result())]);
+                if !matches!(error, SyncError::Registration(_)) {
+                    unavailable_attempts += 1;
+                    if unavailable_attempts >= DEREGISTER_AFTER_FAILURES {
+                        deregister_unavailable(discovery_client, target, status, &cx).await;
+                    }
+                }
+                if let SyncError::Info(_) = error {
+                    info_client = None;
+                }
+                status.failed(error.to_string());
+                let delay = backoff.next_delay();
+                println!("ERROR {}: {}, retrying in {:?}", target.name(), error, delay);
+                delay
+            }
+        };
+
+        cx.span().add_event("end of loop".to_string(), vec![]);
+        cx.span().end();
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// removes a local instance that is no longer available from the Discovery Servers, if it was registered
+async fn deregister_unavailable(discovery_client: &DiscoveryClient, target: &InfoTarget, status: &SyncStatus, cx: &Context) {
+    let state = status.snapshot();
+    let (Some(registered_as), Some(registered_id)) = (state.registered_as, state.registered_id) else {
+        return;
+    };
+    match deregister(discovery_client, registered_as, &registered_id, cx).await {
+        Ok(()) => {
+            status.deregistered();
+            println!("Deregistered unavailable {:?} {} of {}", registered_as, registered_id, target.name());
+            cx.span().add_event("deregistered unavailable instance".to_string(), vec![]);
+        }
+        Err(error) => println!("Failed to deregister unavailable {:?} {} of {}: {}", registered_as, registered_id, target.name(), error),
+    }
+}
+
+/// Deregisters the instance from every Discovery Server, an instance that is already gone counts as deregistered
+pub async fn deregister(discovery_client: &DiscoveryClient, registered_as: RegisteredAs, id: &str, cx: &Context) -> Result<(), ClientError> {
+    match discovery_client.deregister_instance(instance_type(registered_as), id, cx).await {
+        Err(error) if error.code() == tonic::Code::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// asks the local Gitstafette instance for its status, and registers it to the Discovery Server if it is alive
+#[autometrics]
+async fn sync_local_status(info_client: &mut LocalInfoClient, discovery_client: &DiscoveryClient, target: &InfoTarget, status: &SyncStatus, cx: &Context) -> Result<(), SyncError> {
+    let info = match info_client.get_info(cx).await {
+        Ok(response) => {
+            println!("Got successful response!");
+            cx.span().add_event(
+                "Got response!".to_string(),
+                vec![KeyValue::new("status", "OK".to_string())],
+            );
+            response
+        }
+        Err(status) => {
+            cx.span().add_event(
+                "Got response!".to_string(),
+                vec![KeyValue::new("status", status.code().to_string())],
+            );
+            return Err(SyncError::Info(status));
+        }
+    };
+
+    // depending on the response, we should register the server/hub? to the Discovery Server
+    if !info.alive {
+        return Err(SyncError::NotAlive);
+    }
+    status.info_succeeded();
+    cx.span().add_event("local service is alive".to_string(), vec![]);
+
+    // hubs and servers are views of the same instance model, so every type is registered the same way;
+    // instance types this sidecar does not know yet are registered as servers
+    let instance_type = InstanceType::try_from(info.instance_type).unwrap_or(InstanceType::Server);
+    let registered_as = registered_as(instance_type);
+    println!("registering {:?}: {}", registered_as, info.name);
+    let instance = to_instance(&info, instance_type, target);
+    let id = instance.id.to_string();
+    discovery_client.register_instance(instance, cx).await.map_err(SyncError::Registration)?;
+    status.registration_succeeded(registered_as, &id);
+    cx.span().add_event("registered instance".to_string(), vec![KeyValue::new("type", instance_type.as_str_name())]);
+    Ok(())
+}
+
+/// The registration of the local instance, at the host and port it is advertised with
+fn to_instance(info: &GetInfoResponse, instance_type: InstanceType, target: &InfoTarget) -> GitstafetteInstance {
+    let server_info = info.server.as_ref();
+    let host = target.advertise.host(server_info.map_or("", |server_info| server_info.hostname.as_str()));
+    let port = target.advertise.port(server_info.map_or("", |server_info| server_info.port.as_str()));
+    GitstafetteInstance {
+        id: target.registration_id(&host, &port),
+        instance_type: instance_type.into(),
+        name: info.name.to_string(),
+        version: info.version.to_string(),
+        host,
+        port,
+        repositories: server_info.and_then(|server_info| server_info.repositories.clone()).unwrap_or_default(),
+        relay_host: info.relay.as_ref().map(|relay_info| relay_info.hostname.to_string()).unwrap_or_default(),
+        relay_port: info.relay.as_ref().map(|relay_info| relay_info.port.to_string()).unwrap_or_default(),
+        health: None,
+        relay_status: RelayStatus::Unknown.into(),
+        labels: target.labels.clone(),
+        resource_version: 0,
+    }
+}
+
+fn registered_as(instance_type: InstanceType) -> RegisteredAs {
+    match instance_type {
+        InstanceType::Hub => RegisteredAs::Hub,
+        InstanceType::Server => RegisteredAs::Server,
+        InstanceType::Relay => RegisteredAs::Relay,
+        InstanceType::Client => RegisteredAs::Client,
+        InstanceType::Discovery => RegisteredAs::Discovery,
+        InstanceType::Explorer => RegisteredAs::Explorer,
+    }
+}
+
+fn instance_type(registered_as: RegisteredAs) -> InstanceType {
+    match registered_as {
+        RegisteredAs::Hub => InstanceType::Hub,
+        RegisteredAs::Server => InstanceType::Server,
+        RegisteredAs::Relay => InstanceType::Relay,
+        RegisteredAs::Client => InstanceType::Client,
+        RegisteredAs::Discovery => InstanceType::Discovery,
+        RegisteredAs::Explorer => InstanceType::Explorer,
+    }
+}
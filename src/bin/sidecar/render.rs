@@ -0,0 +1,182 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use handlebars::Handlebars;
+use opentelemetry::Context;
+use gsf_discovery::{ClientError, RegistryMirror};
+use serde::Serialize;
+
+use crate::output::view::{HubView, ServerView};
+use crate::retry::backoff::Backoff;
+
+// first delay after a failed poll, doubling with every consecutive failure
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// What to do after the rendered file changed, so the consuming process picks it up
+#[derive(Debug, Default)]
+pub struct Reload {
+    /// signal number to send to the process
+    pub signal: Option<i32>,
+    pub pid: Option<i32>,
+    /// file with the pid of the process, read before every reload as the process may have restarted
+    pub pid_file: Option<String>,
+    /// shell command to run
+    pub command: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct RenderConfig {
+    pub template: PathBuf,
+    pub output: PathBuf,
+    pub interval: Duration,
+    pub max_backoff: Duration,
+    pub reload: Reload,
+}
+
+/// The registry as seen by templates, instances are sorted by id so unchanged registries render the same
+#[derive(Debug, Serialize)]
+struct RegistryView {
+    hubs: Vec<HubView>,
+    servers: Vec<ServerView>,
+}
+
+/// Renders the registry with the template every interval, and reloads the consumer whenever the output changed.
+/// Only returns when the template is invalid.
+pub async fn render_loop(mirror: &RegistryMirror, config: &RenderConfig, cx: &Context) -> Result<(), Box<dyn Error>> {
+    let templates = load_template(&config.template)?;
+    let mut backoff = Backoff::new(INITIAL_BACKOFF, config.max_backoff);
+    loop {
+        // the client fails over to another Discovery Server by itself
+        let delay = match render_once_with(&templates, mirror, config, cx).await {
+            Ok(_) => {
+                backoff.reset();
+                config.interval
+            }
+            Err(error) => {
+                let delay = backoff.next_delay();
+                println!("ERROR rendering {}: {}, retrying in {:?}", config.output.display(), error, delay);
+                delay
+            }
+        };
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Renders the registry with the template once, returns whether the output changed
+pub async fn render_once(mirror: &RegistryMirror, config: &RenderConfig, cx: &Context) -> Result<bool, Box<dyn Error>> {
+    let templates = load_template(&config.template)?;
+    render_once_with(&templates, mirror, config, cx).await
+}
+
+const TEMPLATE_NAME: &str = "template";
+
+fn load_template(path: &Path) -> Result<Handlebars<'static>, Box<dyn Error>> {
+    let mut templates = Handlebars::new();
+    // fail on typos instead of rendering them as empty strings
+    templates.set_strict_mode(true);
+    // the output is a config file, not HTML
+    templates.register_escape_fn(handlebars::no_escape);
+    templates
+        .register_template_file(TEMPLATE_NAME, path)
+        .map_err(|error| format!("invalid template {}: {}", path.display(), error))?;
+    Ok(templates)
+}
+
+async fn render_once_with(templates: &Handlebars<'static>, mirror: &RegistryMirror, config: &RenderConfig, cx: &Context) -> Result<bool, Box<dyn Error>> {
+    let registry = get_registry(mirror, cx).await?;
+    let rendered = templates.render(TEMPLATE_NAME, &registry)?;
+
+    // only an actual change is written, so the consumer is not reloaded every interval
+    if std::fs::read_to_string(&config.output).is_ok_and(|current| current == rendered) {
+        return Ok(false);
+    }
+    write_atomically(&config.output, &rendered)?;
+    println!("Rendered {} with {} hubs and {} servers", config.output.display(), registry.hubs.len(), registry.servers.len());
+    reload(&config.reload).await;
+    Ok(true)
+}
+
+/// The registry from a Discovery Server, or the last good snapshot while none is available
+async fn get_registry(mirror: &RegistryMirror, cx: &Context) -> Result<RegistryView, ClientError> {
+    let snapshot = match mirror.refresh(cx).await {
+        Ok(snapshot) => snapshot,
+        Err(error) => match mirror.current() {
+            Some(snapshot) => {
+                println!("ERROR refreshing the registry: {}, rendering the snapshot from {:?} ago", error, snapshot.age());
+                snapshot
+            }
+            None => return Err(error),
+        },
+    };
+    let mut hubs = snapshot.hubs.clone();
+    let mut servers = snapshot.servers.clone();
+    hubs.sort_by(|a, b| a.id.cmp(&b.id));
+    servers.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(RegistryView {
+        hubs: hubs.into_iter().map(HubView::from).collect(),
+        servers: servers.into_iter().map(ServerView::from).collect(),
+    })
+}
+
+/// Writes next to the output and renames it over the output, so readers never see a partially written file
+fn write_atomically(output: &Path, content: &str) -> std::io::Result<()> {
+    let file_name = output.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let temporary = output.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+    {
+        let mut file = std::fs::File::create(&temporary)?;
+        std::io::Write::write_all(&mut file, content.as_bytes())?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&temporary, output).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temporary);
+    })
+}
+
+async fn reload(reload: &Reload) {
+    if let Some(signal) = reload.signal {
+        let pid = match (&reload.pid, &reload.pid_file) {
+            (Some(pid), _) => Some(*pid),
+            (None, Some(pid_file)) => match std::fs::read_to_string(pid_file).map(|content| content.trim().parse::<i32>()) {
+                Ok(Ok(pid)) => Some(pid),
+                _ => {
+                    println!("ERROR reading pid from {}", pid_file);
+                    None
+                }
+            },
+            (None, None) => None,
+        };
+        if let Some(pid) = pid {
+            println!("Sending signal {} to pid {}", signal, pid);
+            // SAFETY: kill has no memory safety requirements
+            if unsafe { libc::kill(pid, signal) } != 0 {
+                println!("ERROR sending signal {} to pid {}: {}", signal, pid, std::io::Error::last_os_error());
+            }
+        }
+    }
+    if let Some(command) = &reload.command {
+        println!("Running reload command: {}", command);
+        match tokio::process::Command::new("sh").arg("-c").arg(command).status().await {
+            Ok(status) if status.success() => {}
+            Ok(status) => println!("ERROR reload command exited with {}", status),
+            Err(error) => println!("ERROR running reload command: {}", error),
+        }
+    }
+}
+
+/// Parses a signal name such as `HUP` or `SIGHUP`, or a signal number
+pub fn parse_signal(signal: &str) -> Result<i32, String> {
+    if let Ok(number) = signal.parse::<i32>() {
+        return Ok(number);
+    }
+    let name = signal.to_uppercase();
+    match name.strip_prefix("SIG").unwrap_or(&name) {
+        "HUP" => Ok(libc::SIGHUP),
+        "INT" => Ok(libc::SIGINT),
+        "QUIT" => Ok(libc::SIGQUIT),
+        "TERM" => Ok(libc::SIGTERM),
+        "USR1" => Ok(libc::SIGUSR1),
+        "USR2" => Ok(libc::SIGUSR2),
+        _ => Err(format!("unsupported signal {}", signal)),
+    }
+}
\ No newline at end of file
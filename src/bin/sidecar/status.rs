@@ -0,0 +1,180 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use opentelemetry::metrics::MeterProvider;
+use opentelemetry::{global, KeyValue};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RegisteredAs {
+    Hub,
+    Server,
+    /// any other instance type, registered as a generic instance
+    Relay,
+    Client,
+    Discovery,
+    Explorer,
+}
+
+/// Outcome of the most recent steps of the info registration loop, times are unix timestamps (seconds)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncState {
+    pub last_attempt: Option<u64>,
+    pub last_info_success: Option<u64>,
+    pub last_registration_success: Option<u64>,
+    /// what the local instance was last registered as, so it can be deregistered
+    pub registered_as: Option<RegisteredAs>,
+    /// the id it was last registered with, which may be derived from the host and port its Info service reported
+    pub registered_id: Option<String>,
+    pub last_failure: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TargetReport {
+    pub target: String,
+    pub ok: bool,
+    #[serde(flatten)]
+    pub state: SyncState,
+}
+
+/// The sidecar is ok when every target is
+#[derive(Debug, Serialize)]
+pub struct StatusReport {
+    pub ok: bool,
+    pub targets: Vec<TargetReport>,
+}
+
+/// Shared between the info registration loop, which records its progress, and the health endpoints
+#[derive(Debug, Clone)]
+pub struct SyncStatus {
+    state: Arc<Mutex<SyncState>>,
+    /// the sidecar is ready while both the Info poll and the registration succeeded within this age
+    ready_max_age: Duration,
+    /// the sidecar is healthy while the loop made an attempt within this age
+    live_max_age: Duration,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn within(timestamp: Option<u64>, max_age: Duration) -> bool {
+    timestamp.is_some_and(|timestamp| now().saturating_sub(timestamp) <= max_age.as_secs())
+}
+
+impl SyncStatus {
+    pub fn new(ready_max_age: Duration, live_max_age: Duration) -> Self {
+        SyncStatus {
+            state: Arc::new(Mutex::new(SyncState::default())),
+            ready_max_age,
+            live_max_age,
+        }
+    }
+
+    pub fn info_succeeded(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.last_attempt = Some(now());
+        state.last_info_success = state.last_attempt;
+    }
+
+    pub fn registration_succeeded(&self, registered_as: RegisteredAs, id: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.last_attempt = Some(now());
+        state.last_registration_success = state.last_attempt;
+        state.registered_as = Some(registered_as);
+        state.registered_id = Some(id.to_string());
+    }
+
+    pub fn failed(&self, error: String) {
+        let mut state = self.state.lock().unwrap();
+        state.last_attempt = Some(now());
+        state.last_failure = state.last_attempt;
+        state.last_error = Some(error);
+    }
+
+    pub fn deregistered(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.registered_as = None;
+        state.registered_id = None;
+    }
+
+    pub fn snapshot(&self) -> SyncState {
+        self.state.lock().unwrap().clone()
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        let state = self.snapshot();
+        // the loop has not made its first attempt yet
+        if state.last_attempt.is_none() {
+            return true;
+        }
+        within(state.last_attempt, self.live_max_age)
+    }
+
+    pub fn is_ready(&self) -> bool {
+        let state = self.snapshot();
+        within(state.last_info_success, self.ready_max_age) && within(state.last_registration_success, self.ready_max_age)
+    }
+}
+
+/// The sync status of every target of the sidecar, by target name
+#[derive(Debug, Clone)]
+pub struct SidecarStatus {
+    targets: Arc<Vec<(String, SyncStatus)>>,
+}
+
+impl SidecarStatus {
+    pub fn new(targets: Vec<(String, SyncStatus)>) -> Self {
+        SidecarStatus {
+            targets: Arc::new(targets),
+        }
+    }
+
+    fn report(&self, check: impl Fn(&SyncStatus) -> bool) -> (StatusCode, Json<StatusReport>) {
+        let targets: Vec<TargetReport> = self
+            .targets
+            .iter()
+            .map(|(target, status)| TargetReport {
+                target: target.to_string(),
+                ok: check(status),
+                state: status.snapshot(),
+            })
+            .collect();
+        let ok = targets.iter().all(|target| target.ok);
+        let code = if ok { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+        (code, Json(StatusReport { ok, targets }))
+    }
+
+    /// exposes the readiness of every target as the gauge `gsf_sidecar_target_ready{target}`
+    pub fn register_metrics(&self) {
+        let targets = self.targets.clone();
+        let meter = global::meter_provider().meter("gsf-discovery");
+        let _gauge = meter
+            .u64_observable_gauge("gsf_sidecar_target_ready")
+            .with_description("Whether the target was recently polled and registered (1) or not (0)")
+            .with_callback(move |observer| {
+                for (target, status) in targets.iter() {
+                    observer.observe(status.is_ready() as u64, &[KeyValue::new("target", target.to_string())]);
+                }
+            })
+            .init();
+    }
+}
+
+/// Liveness: fails only when the info registration loop of a target stopped making attempts
+pub async fn healthz(State(status): State<SidecarStatus>) -> (StatusCode, Json<StatusReport>) {
+    status.report(SyncStatus::is_healthy)
+}
+
+/// Readiness: fails when a local instance or the Discovery Server has not been reached recently
+pub async fn readyz(State(status): State<SidecarStatus>) -> (StatusCode, Json<StatusReport>) {
+    status.report(SyncStatus::is_ready)
+}
\ No newline at end of file
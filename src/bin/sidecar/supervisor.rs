@@ -0,0 +1,94 @@
+use std::error::Error;
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+use std::time::Duration;
+
+use gsf_discovery::DiscoveryClient;
+use opentelemetry::Context;
+use tokio::process::Command;
+use tokio::signal::unix::{signal, SignalKind};
+use tonic::transport::Endpoint;
+
+use crate::sidecar::registration::{deregister, sync_local_status_to_discovery_server};
+use crate::sidecar::target::InfoTarget;
+use crate::sidecar::status::{RegisteredAs, SyncStatus};
+
+// how long deregistering may take before the supervisor exits anyway
+const DEREGISTER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runs the command as child process and keeps it registered while its Info service reports it is alive.
+/// Signals sent to the supervisor are forwarded to the child.
+/// When the child stops it is deregistered, and its exit code is returned.
+pub async fn run_supervised(discovery_client: &DiscoveryClient, target: &InfoTarget, interval: Duration, max_backoff: Duration, command: &[String], cx: &Context) -> Result<i32, Box<dyn Error>> {
+    let (program, args) = command.split_first().ok_or("no command to run")?;
+    // fail on an invalid Info address before starting the child
+    Endpoint::from_shared(target.address())?;
+
+    let mut child = Command::new(program).args(args).spawn()?;
+    let pid = child.id().ok_or("child process exited immediately")?;
+    println!("Started {} with pid {}", program, pid);
+    let forwarders = forward_signals(pid)?;
+
+    // readiness is not served in this mode, the ages only need to be valid
+    let status = SyncStatus::new(interval, interval);
+    let exit_status = tokio::select! {
+        exit_status = child.wait() => exit_status?,
+        result = sync_local_status_to_discovery_server(discovery_client, target, interval, max_backoff, &status) => {
+            // the registration loop only stops on invalid configuration
+            child.kill().await?;
+            result?;
+            child.wait().await?
+        }
+    };
+    for forwarder in forwarders {
+        forwarder.abort();
+    }
+    println!("{} exited with {}", program, exit_status);
+
+    let state = status.snapshot();
+    if let (Some(registered_as), Some(registered_id)) = (state.registered_as, state.registered_id) {
+        deregister_child(discovery_client, registered_as, &registered_id, cx).await;
+    }
+    Ok(exit_code(exit_status))
+}
+
+fn forward_signals(pid: u32) -> std::io::Result<Vec<tokio::task::JoinHandle<()>>> {
+    let mut forwarders = Vec::new();
+    for kind in [
+        SignalKind::terminate(),
+        SignalKind::interrupt(),
+        SignalKind::hangup(),
+        SignalKind::quit(),
+        SignalKind::user_defined1(),
+        SignalKind::user_defined2(),
+    ] {
+        let mut signals = signal(kind)?;
+        forwarders.push(tokio::spawn(async move {
+            while signals.recv().await.is_some() {
+                println!("Forwarding signal {} to pid {}", kind.as_raw_value(), pid);
+                // SAFETY: kill has no memory safety requirements, the pid belongs to our child
+                unsafe {
+                    libc::kill(pid as libc::pid_t, kind.as_raw_value());
+                }
+            }
+        }));
+    }
+    Ok(forwarders)
+}
+
+/// deregisters from every Discovery Server, as the child may have been registered with another one after a failover
+async fn deregister_child(discovery_client: &DiscoveryClient, registered_as: RegisteredAs, id: &str, cx: &Context) {
+    let deregistration = deregister(discovery_client, registered_as, id, cx);
+    match tokio::time::timeout(DEREGISTER_TIMEOUT, deregistration).await {
+        Ok(Ok(())) => println!("Deregistered {:?} {}", registered_as, id),
+        Ok(Err(error)) => println!("Failed to deregister {:?} {}: {}", registered_as, id, error),
+        Err(_) => println!("Timed out deregistering {:?} {}", registered_as, id),
+    }
+}
+
+/// The exit code of the child, or 128 + the signal that stopped it, as shells report it
+fn exit_code(exit_status: ExitStatus) -> i32 {
+    exit_status
+        .code()
+        .unwrap_or_else(|| 128 + exit_status.signal().unwrap_or_default())
+}
@@ -0,0 +1,159 @@
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::str::FromStr;
+
+use crate::sidecar::advertise::Advertise;
+use crate::sidecar::info::{InfoSource, StaticInfo};
+
+/// path of the Info endpoint of targets that serve it as JSON over HTTP
+pub const DEFAULT_INFO_PATH: &str = "/info";
+
+/// A local Gitstafette instance whose Info service is polled by the sidecar
+#[derive(Debug, Clone)]
+pub struct InfoTarget {
+    pub host: String,
+    pub port: String,
+    pub protocol: String,
+    /// id the instance is registered with in the Discovery Server, when empty the advertised `host:port`
+    pub id: String,
+    /// overrides for the host and port the instance is registered with
+    pub advertise: Advertise,
+    pub source: InfoSource,
+    /// labels the instance is registered with, e.g. `env=prod`
+    pub labels: BTreeMap<String, String>,
+}
+
+impl InfoTarget {
+    pub fn address(&self) -> String {
+        format!("{}://{}:{}", self.protocol, self.host, self.port)
+    }
+
+    /// name of the target in logs, metrics and health reports
+    pub fn name(&self) -> String {
+        if self.id.is_empty() {
+            format!("{}:{}", self.host, self.port)
+        } else {
+            self.id.to_string()
+        }
+    }
+
+    /// The configured id, or the `host:port` the instance is registered with.
+    /// Not the polled address, which is the same `127.0.0.1:port` for the sidecars on every node.
+    pub fn registration_id(&self, advertised_host: &str, advertised_port: &str) -> String {
+        if self.id.is_empty() {
+            format!("{}:{}", advertised_host, advertised_port)
+        } else {
+            self.id.to_string()
+        }
+    }
+}
+
+/// Parses `[id@][mode+][protocol://]host:port[/path]`, the protocol defaults to http.
+/// Without an id, the instance is registered as the `host:port` it is advertised with.
+/// The mode is `json` for an HTTP endpoint at the path (default `/info`) or `health` for the gRPC health service,
+/// without it the gRPC Info service is used.
+impl FromStr for InfoTarget {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let spec = spec.trim();
+        let invalid = || format!("invalid target {:?}, expected [id@][mode+][protocol://]host:port[/path]", spec);
+        let (id, address) = match spec.split_once('@') {
+            Some((id, address)) => (id.to_string(), address),
+            None => (String::new(), spec),
+        };
+        let (scheme, host_port) = match address.split_once("://") {
+            Some((scheme, host_port)) => (scheme, host_port),
+            None => ("http", address),
+        };
+        let (mode, protocol) = match scheme.split_once('+') {
+            Some((mode, protocol)) => (Some(mode), protocol.to_string()),
+            None => (None, scheme.to_string()),
+        };
+        let (host_port, path) = match host_port.find('/') {
+            Some(index) => host_port.split_at(index),
+            None => (host_port, ""),
+        };
+        let source = match mode {
+            None if path.is_empty() => InfoSource::Grpc,
+            Some("json") if path.is_empty() => InfoSource::Http { path: DEFAULT_INFO_PATH.to_string() },
+            Some("json") => InfoSource::Http { path: path.to_string() },
+            Some("health") if path.is_empty() => InfoSource::Health(StaticInfo::default()),
+            _ => return Err(invalid()),
+        };
+        let (host, port) = host_port.rsplit_once(':').ok_or_else(invalid)?;
+        if host.is_empty() || port.parse::<u16>().is_err() {
+            return Err(invalid());
+        }
+        Ok(InfoTarget { host: host.to_string(), port: port.to_string(), protocol, id, advertise: Advertise::default(), source, labels: BTreeMap::new() })
+    }
+}
+
+/// Reads one target per line, in the same format as `--target`; empty lines and lines starting with `#` are skipped
+pub fn read_targets_file(path: &str) -> Result<Vec<InfoTarget>, Box<dyn Error>> {
+    let content = std::fs::read_to_string(path).map_err(|error| format!("failed to read {}: {}", path, error))?;
+    let mut targets = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        targets.push(line.parse::<InfoTarget>()?);
+    }
+    Ok(targets)
+}
+
+/// Every target is registered and reported on its own, so their names have to be unique
+pub fn check_unique_names(targets: &[InfoTarget]) -> Result<(), String> {
+    let mut names = HashSet::new();
+    for target in targets {
+        if !names.insert(target.name()) {
+            return Err(format!("target {} is configured more than once", target.name()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_targets() {
+        let target: InfoTarget = "hub-1@127.0.0.1:50051".parse().unwrap();
+        assert_eq!((target.id.as_str(), target.host.as_str(), target.port.as_str(), target.protocol.as_str()), ("hub-1", "127.0.0.1", "50051", "http"));
+        assert!(matches!(target.source, InfoSource::Grpc));
+
+        let target: InfoTarget = " https://localhost:50052 ".parse().unwrap();
+        assert_eq!((target.id.as_str(), target.host.as_str(), target.protocol.as_str()), ("", "localhost", "https"));
+
+        let target: InfoTarget = "json+http://127.0.0.1:8080".parse().unwrap();
+        assert!(matches!(target.source, InfoSource::Http { path } if path == DEFAULT_INFO_PATH));
+        let target: InfoTarget = "relay-1@json+http://127.0.0.1:8080/status".parse().unwrap();
+        assert!(matches!(target.source, InfoSource::Http { path } if path == "/status"));
+        assert_eq!(target.id, "relay-1");
+
+        let target: InfoTarget = "health+http://[::1]:50053".parse().unwrap();
+        assert!(matches!(target.source, InfoSource::Health(_)));
+        assert_eq!((target.host.as_str(), target.port.as_str()), ("[::1]", "50053"));
+    }
+
+    #[test]
+    fn rejects_invalid_targets() {
+        for spec in [
+            "", "127.0.0.1", "127.0.0.1:", ":50051", "127.0.0.1:http", "127.0.0.1:70000", "hub-1@", "grpc+http://127.0.0.1:50051",
+            "127.0.0.1:50051/info", "health+http://127.0.0.1:50051/info",
+        ] {
+            assert!(spec.parse::<InfoTarget>().is_err(), "{:?} should not parse", spec);
+        }
+    }
+
+    #[test]
+    fn registers_without_id_as_advertised_address() {
+        let target: InfoTarget = "127.0.0.1:50051".parse().unwrap();
+        assert_eq!(target.name(), "127.0.0.1:50051");
+        assert_eq!(target.registration_id("10.0.0.7", "50051"), "10.0.0.7:50051");
+        let target: InfoTarget = "hub-1@127.0.0.1:50051".parse().unwrap();
+        assert_eq!(target.registration_id("10.0.0.7", "50051"), "hub-1");
+    }
+}
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use tokio::sync::broadcast;
+use tonic::Status;
+
+use crate::store::events::StoreEvent;
+use crate::store::model::{GSFHealth, GSFInstance, InstanceType, RelayStatus};
+
+#[derive(Debug, Clone)]
+pub enum StoreError {
+  /// the backend cannot be reached right now, the call can be retried
+  Unavailable(String),
+  /// the backend failed in a way a retry does not fix
+  Internal(String),
+  /// the registration is not at the expected resource version, it changed since it was read
+  Conflict(String),
+}
+
+impl std::fmt::Display for StoreError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      StoreError::Unavailable(message) => write!(f, "store unavailable: {}", message),
+      StoreError::Internal(message) => write!(f, "store failed: {}", message),
+      StoreError::Conflict(message) => write!(f, "conflict: {}", message),
+    }
+  }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<StoreError> for Status {
+  fn from(error: StoreError) -> Self {
+    match error {
+      StoreError::Unavailable(message) => Status::unavailable(message),
+      StoreError::Internal(message) => Status::internal(message),
+      StoreError::Conflict(message) => Status::aborted(message),
+    }
+  }
+}
+
+pub type StoreResult<T> = Result<T, StoreError>;
+
+/// The registry, shared by the gRPC service, the prober and the web server.
+/// Every namespace has its own instances, an instance is only found in the namespace it was registered in.
+/// Every change of a registration gives it a new resource version, the revision of the Store after the change.
+/// Entries are returned as shared references, so reading the registry does not copy it.
+/// Backends that need I/O, such as a database or a remote key-value store, implement it by reporting
+/// their failures as a StoreError instead of blocking or panicking.
+#[tonic::async_trait]
+pub trait Store: std::fmt::Debug + Clone + Send + Sync + 'static {
+  /// Adds or replaces the instance with the same type and id in the namespace, and returns its resource version.
+  /// With an expected version, only replaces the instance at that version, and fails with a Conflict otherwise.
+  async fn add_instance(&self, namespace: &str, instance: GSFInstance, expected_version: Option<u64>) -> StoreResult<u64>;
+  async fn get_instance(&self, namespace: &str, instance_type: InstanceType, id: &str) -> StoreResult<Option<Arc<GSFInstance>>>;
+  /// The instances in the namespace of one type, or of every type when None
+  async fn get_instances(&self, namespace: &str, instance_type: Option<InstanceType>) -> StoreResult<Vec<Arc<GSFInstance>>>;
+  /// Returns the removed instance, or None if it was not registered in the namespace
+  async fn remove_instance(&self, namespace: &str, instance_type: InstanceType, id: &str) -> StoreResult<Option<Arc<GSFInstance>>>;
+  /// Records the result of probing the instance at the resource version that was probed, only a change of its health status is published as an update.
+  /// Does nothing when the instance was re-registered since, the result may be of its previous endpoint.
+  async fn set_health(&self, namespace: &str, instance_type: InstanceType, id: &str, resource_version: u64, health: GSFHealth) -> StoreResult<()>;
+  /// Records the result of probing the relay of the instance at the resource version that was probed, a change of its status is published as an update
+  async fn set_relay_status(&self, namespace: &str, instance_type: InstanceType, id: &str, resource_version: u64, relay_status: RelayStatus) -> StoreResult<()>;
+  /// The namespaces that have instances, sorted by name
+  async fn namespaces(&self) -> StoreResult<Vec<String>>;
+
+  /// Events published by this Discovery Server, from the moment of subscribing
+  fn subscribe(&self) -> broadcast::Receiver<StoreEvent>;
+  /// The revision of the last event, cheap enough to read on every listing
+  async fn revision(&self) -> StoreResult<u64>;
+  /// Returns the events after the given revision, or None if they are no longer in the history
+  async fn events_since(&self, revision: u64) -> StoreResult<Option<Vec<StoreEvent>>>;
+}
\ No newline at end of file
@@ -0,0 +1,75 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+
+use crate::store::model::{GSFInstance, InstanceType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventType {
+  Added,
+  Updated,
+  Removed,
+}
+
+/// A mutation of the Store, as published to subscribers (e.g., webhooks)
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreEvent {
+  /// revision of the Store after this event, assigned by the Store when the event is published
+  pub revision: u64,
+  #[serde(rename = "type")]
+  pub event_type: EventType,
+  pub namespace: String,
+  pub instance: InstanceType,
+  pub id: String,
+  pub timestamp: u64,
+  pub before: Option<GSFInstance>,
+  pub after: Option<GSFInstance>,
+}
+
+impl StoreEvent {
+  /// Creates the event for an instance in the namespace going from `before` to `after`.
+  /// Returns None if nothing changed, so re-registrations do not produce events.
+  pub fn for_instance(namespace: &str, before: Option<GSFInstance>, after: Option<GSFInstance>) -> Option<StoreEvent> {
+    if before == after {
+      return None;
+    }
+    let (instance, id) = after
+      .as_ref()
+      .or(before.as_ref())
+      .map(|instance| (instance.instance_type, instance.id.to_string()))?;
+    Some(StoreEvent::new(namespace.to_string(), instance, id, before, after))
+  }
+
+  fn new(namespace: String, instance: InstanceType, id: String, before: Option<GSFInstance>, after: Option<GSFInstance>) -> StoreEvent {
+    let event_type = match (&before, &after) {
+      (None, _) => EventType::Added,
+      (Some(_), Some(_)) => EventType::Updated,
+      (Some(_), None) => EventType::Removed,
+    };
+    let timestamp = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|duration| duration.as_secs())
+      .unwrap_or_default();
+
+    StoreEvent {
+      revision: 0,
+      event_type,
+      namespace,
+      instance,
+      id,
+      timestamp,
+      before,
+      after,
+    }
+  }
+
+  /// Name of the event as used in webhook headers, e.g., `hub.updated` or `relay.added`
+  pub fn name(&self) -> String {
+    let event_type = match self.event_type {
+      EventType::Added => "added",
+      EventType::Updated => "updated",
+      EventType::Removed => "removed",
+    };
+    format!("{}.{}", self.instance.name(), event_type)
+  }
+}
@@ -1,120 +1,381 @@
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use arc_swap::ArcSwap;
+use tokio::sync::{broadcast, Mutex, MutexGuard};
 
+use crate::store::backend::{Store, StoreError, StoreResult};
+use crate::store::events::StoreEvent;
+use crate::store::model::{GSFHealth, GSFInstance, InstanceType, RelayStatus};
 
-#[derive(Debug, Clone)]
-pub struct GSFHub {
-  pub id: String,
-  pub name: String,
-  pub version: String,
-  pub host: String,
-  pub port: String,
-  pub repositories: String,
-  pub relay_host: String,
-  pub relay_port: String,
+// number of events buffered for subscribers, slower subscribers will miss events
+const EVENT_BUFFER_SIZE: usize = 1024;
+// number of past events kept to let subscribers resume from an earlier revision
+const EVENT_HISTORY_SIZE: usize = 1000;
+
+#[derive(Debug, Default)]
+struct EventLog {
+  history: VecDeque<StoreEvent>,
 }
 
-#[derive(Debug, Clone)]
-pub struct GSFServer {
-  pub id: String,
-  pub name: String,
-  pub version: String,
-  pub host: String,
-  pub port: String,
-  pub repositories: String
+/// An immutable version of the registry, unchanged namespaces and entries are shared with the previous version
+#[derive(Debug, Clone, Default)]
+struct Registry {
+  /// only namespaces with instances
+  namespaces: HashMap<String, Arc<Namespace>>,
+  /// the revision of the last event, kept with the snapshot so reading it does not lock the event log
+  revision: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Namespace {
+  /// by instance type, then by id
+  instances: HashMap<InstanceType, HashMap<String, Arc<GSFInstance>>>,
 }
 
+impl Registry {
+  fn get(&self, namespace: &str, instance_type: InstanceType, id: &str) -> Option<&Arc<GSFInstance>> {
+    self.namespaces
+      .get(namespace)
+      .and_then(|namespace| namespace.instances.get(&instance_type))
+      .and_then(|instances| instances.get(id))
+  }
+
+  fn get_mut(&mut self, namespace: &str, instance_type: InstanceType, id: &str) -> Option<&mut Arc<GSFInstance>> {
+    self.namespaces
+      .get_mut(namespace)
+      .and_then(|namespace| Arc::make_mut(namespace).instances.get_mut(&instance_type))
+      .and_then(|instances| instances.get_mut(id))
+  }
 
-// Define the API interface
-pub trait Store {
-  fn new() -> Self;
-  fn add_hub(&self, hub: GSFHub);
-  fn get_hub(&self, id: String) -> Option<GSFHub>;
-  fn get_hubs(&self) -> Vec<GSFHub>;
-  fn update_hub(&self, hub: GSFHub);
-  fn remove_hub(&self, id: String);
-
-  fn add_server(&self, hub: GSFServer);
-  fn get_server(&self, id: String) -> Option<GSFServer>;
-  fn get_servers(&self) -> Vec<GSFServer>;
-  fn update_server(&self, hub: GSFServer);
-  fn remove_server(&self, id: String);
+  fn insert(&mut self, namespace: &str, instance: GSFInstance) -> Option<Arc<GSFInstance>> {
+    let namespace = self.namespaces.entry(namespace.to_string()).or_default();
+    Arc::make_mut(namespace)
+      .instances
+      .entry(instance.instance_type)
+      .or_default()
+      .insert(instance.id.clone(), Arc::new(instance))
+  }
+
+  fn remove(&mut self, namespace: &str, instance_type: InstanceType, id: &str) -> Option<Arc<GSFInstance>> {
+    let entry = Arc::make_mut(self.namespaces.get_mut(namespace)?);
+    let removed = entry.instances.get_mut(&instance_type).and_then(|instances| instances.remove(id));
+    // an empty namespace is not listed
+    if entry.instances.values().all(HashMap::is_empty) {
+      self.namespaces.remove(namespace);
+    }
+    removed
+  }
 }
 
-#[derive(Debug)]
+/// The registry in memory, lost on restart.
+/// Readers load the current version without locking, so they never wait for registrations.
+/// Writers take turns building the next version and swap it in atomically.
+#[derive(Debug, Clone)]
 pub struct InMemoryStore {
-    hubs: Arc<Mutex<HashMap<String, GSFHub>>>,
-    servers: Arc<Mutex<HashMap<String, GSFServer>>>,
+    registry: Arc<ArcSwap<Registry>>,
+    /// held while building and swapping in the next version, and publishing its event
+    writer: Arc<Mutex<()>>,
+    events: broadcast::Sender<StoreEvent>,
+    event_log: Arc<Mutex<EventLog>>,
 }
 
 impl Default for InMemoryStore {
   fn default() -> Self {
     InMemoryStore {
-      hubs: Arc::new(Mutex::new(HashMap::new())),
-      servers: Arc::new(Mutex::new(HashMap::new())),
+      registry: Arc::new(ArcSwap::from_pointee(Registry::default())),
+      writer: Arc::new(Mutex::new(())),
+      events: broadcast::channel(EVENT_BUFFER_SIZE).0,
+      event_log: Arc::new(Mutex::new(EventLog::default())),
     }
   }
 }
 
+impl InMemoryStore {
+  pub fn new() -> Self {
+    InMemoryStore::default()
+  }
+
+  /// Waits for the other writers, the returned version is current until the guard is dropped
+  async fn write(&self) -> (MutexGuard<'_, ()>, Registry) {
+    let writer = self.writer.lock().await;
+    let next = Registry::clone(&self.registry.load());
+    (writer, next)
+  }
+
+  /// Makes the next version current and publishes its event at the next revision, the writer guard must still be held
+  async fn commit(&self, mut next: Registry, event: Option<StoreEvent>) {
+    let Some(mut event) = event else {
+      self.registry.store(Arc::new(next));
+      return;
+    };
+    // the log stays locked while swapping and sending, so the revision of the snapshot matches the log
+    // and subscribers receive events in revision order
+    let mut event_log = self.event_log.lock().await;
+    next.revision += 1;
+    event.revision = next.revision;
+    self.registry.store(Arc::new(next));
+    if event_log.history.len() >= EVENT_HISTORY_SIZE {
+      event_log.history.pop_front();
+    }
+    event_log.history.push_back(event.clone());
+    // an error only means there are no subscribers
+    let _ = self.events.send(event);
+  }
+
+  /// The revision the next event is published at, the writer guard must be held until it is
+  fn next_revision(&self) -> u64 {
+    self.registry.load().revision + 1
+  }
+}
+
+#[tonic::async_trait]
 impl Store for InMemoryStore {
-  fn new() -> Self {
-    InMemoryStore {
-        hubs: Arc::new(Mutex::new(HashMap::new())),
-        servers: Arc::new(Mutex::new(HashMap::new())),
+  async fn add_instance(&self, namespace: &str, mut instance: GSFInstance, expected_version: Option<u64>) -> StoreResult<u64> {
+    let _writer = self.writer.lock().await;
+    let current = self.registry.load();
+    let existing = current.get(namespace, instance.instance_type, &instance.id);
+    if let Some(expected_version) = expected_version {
+      let name = instance.instance_type.name();
+      match existing {
+        None => return Err(StoreError::Conflict(format!("{} {} is not registered", name, instance.id))),
+        Some(existing) if existing.resource_version != expected_version => {
+          return Err(StoreError::Conflict(format!("{} {} is at resource version {}, not {}", name, instance.id, existing.resource_version, expected_version)));
+        }
+        Some(_) => {}
+      }
     }
+    // re-registering the same endpoint keeps the probe results
+    if let Some(existing) = existing {
+      if existing.host == instance.host && existing.port == instance.port {
+        instance.health = existing.health.clone();
+      }
+      if existing.relay_host == instance.relay_host && existing.relay_port == instance.relay_port {
+        instance.relay_status = existing.relay_status;
+      }
+      // the periodic re-registration of an unchanged instance does not need a new version
+      instance.resource_version = existing.resource_version;
+      if **existing == instance {
+        return Ok(instance.resource_version);
+      }
+    }
+    instance.resource_version = self.next_revision();
+    println!("Added {} in namespace {}: {:?}", instance.instance_type.name(), namespace, instance);
+    let mut next = Registry::clone(&current);
+    let previous = next.insert(namespace, instance.clone());
+    let resource_version = instance.resource_version;
+    self.commit(next, StoreEvent::for_instance(namespace, previous.as_deref().cloned(), Some(instance))).await;
+    Ok(resource_version)
   }
 
-  fn add_hub(&self, gsfhub: GSFHub) {
-    let mut hubs = self.hubs.lock().unwrap();
-    println!("Added hub: {:?}", gsfhub);
-    hubs.insert(gsfhub.id.clone(), gsfhub);
+  async fn get_instance(&self, namespace: &str, instance_type: InstanceType, id: &str) -> StoreResult<Option<Arc<GSFInstance>>> {
+    Ok(self.registry.load().get(namespace, instance_type, id).cloned())
+  }
 
+  async fn get_instances(&self, namespace: &str, instance_type: Option<InstanceType>) -> StoreResult<Vec<Arc<GSFInstance>>> {
+    let registry = self.registry.load();
+    let Some(namespace) = registry.namespaces.get(namespace) else {
+      return Ok(vec![]);
+    };
+    let instances = match instance_type {
+      Some(instance_type) => namespace.instances.get(&instance_type).map(|instances| instances.values().cloned().collect()),
+      None => Some(namespace.instances.values().flat_map(|instances| instances.values().cloned()).collect()),
+    };
+    Ok(instances.unwrap_or_default())
   }
 
-  fn get_hub(&self, id: String) -> Option<GSFHub> {
-    let hubs = self.hubs.lock().unwrap();
-    hubs.get(&id).cloned()
+  async fn remove_instance(&self, namespace: &str, instance_type: InstanceType, id: &str) -> StoreResult<Option<Arc<GSFInstance>>> {
+    let (_writer, mut next) = self.write().await;
+    let previous = next.remove(namespace, instance_type, id);
+    if previous.is_some() {
+      self.commit(next, StoreEvent::for_instance(namespace, previous.as_deref().cloned(), None)).await;
+    }
+    Ok(previous)
   }
 
-  fn get_hubs(&self) -> Vec<GSFHub> {
-    let hubs = self.hubs.lock().unwrap();
-    hubs.values().cloned().collect()
+  async fn set_health(&self, namespace: &str, instance_type: InstanceType, id: &str, resource_version: u64, health: GSFHealth) -> StoreResult<()> {
+    let (_writer, mut next) = self.write().await;
+    let probed = next.get_mut(namespace, instance_type, id).filter(|instance| instance.resource_version == resource_version);
+    if let Some(instance) = probed {
+      let before = GSFInstance::clone(instance);
+      Arc::make_mut(instance).health = health;
+      // becoming healthy or unhealthy changes the listings, new latencies do not
+      let event = (before.health.status != instance.health.status)
+        .then(|| StoreEvent::for_instance(namespace, Some(before), Some(GSFInstance::clone(instance))))
+        .flatten();
+      self.commit(next, event).await;
+    }
+    Ok(())
   }
 
-  fn update_hub(&self, gsfhub: GSFHub) {
-    let mut hubs = self.hubs.lock().unwrap();
-    hubs.insert(gsfhub.id.clone(), gsfhub);
+  async fn set_relay_status(&self, namespace: &str, instance_type: InstanceType, id: &str, resource_version: u64, relay_status: RelayStatus) -> StoreResult<()> {
+    let (_writer, mut next) = self.write().await;
+    let probed = next.get_mut(namespace, instance_type, id).filter(|instance| instance.resource_version == resource_version);
+    if let Some(instance) = probed {
+      let before = GSFInstance::clone(instance);
+      Arc::make_mut(instance).relay_status = relay_status;
+      let event = (before.relay_status != relay_status)
+        .then(|| StoreEvent::for_instance(namespace, Some(before), Some(GSFInstance::clone(instance))))
+        .flatten();
+      self.commit(next, event).await;
+    }
+    Ok(())
   }
 
-  fn remove_hub(&self, id: String) {
-    let mut hubs = self.hubs.lock().unwrap();
-    hubs.remove(&id);
+  async fn namespaces(&self) -> StoreResult<Vec<String>> {
+    let mut namespaces: Vec<String> = self.registry.load().namespaces.keys().cloned().collect();
+    namespaces.sort();
+    Ok(namespaces)
   }
 
-  fn add_server(&self, gsfserver: GSFServer) {
-    let mut servers = self.servers.lock().unwrap();
-    println!("Added server: {:?}", gsfserver);
-    servers.insert(gsfserver.id.clone(), gsfserver);
+  fn subscribe(&self) -> broadcast::Receiver<StoreEvent> {
+    self.events.subscribe()
   }
 
-  fn get_server(&self, id: String) -> Option<GSFServer> {
-    let servers = self.servers.lock().unwrap();
-    servers.get(&id).cloned()
+  async fn revision(&self) -> StoreResult<u64> {
+    Ok(self.registry.load().revision)
   }
 
-  fn get_servers(&self) -> Vec<GSFServer> {
-    let servers = self.servers.lock().unwrap();
-    servers.values().cloned().collect()
+  async fn events_since(&self, revision: u64) -> StoreResult<Option<Vec<StoreEvent>>> {
+    let event_log = self.event_log.lock().await;
+    // the snapshot is swapped while the log is locked, so its revision is the one of the last event in the log
+    let current = self.registry.load().revision;
+    if revision == current {
+      return Ok(Some(vec![]));
+    }
+    // a revision from the future means the Store was restarted since
+    if revision > current {
+      return Ok(None);
+    }
+    // the oldest event we still have must directly follow the requested revision
+    let Some(oldest) = event_log.history.front().map(|event| event.revision) else {
+      return Ok(None);
+    };
+    if revision + 1 < oldest {
+      return Ok(None);
+    }
+    Ok(Some(event_log.history.iter().filter(|event| event.revision > revision).cloned().collect()))
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::BTreeMap;
+
+  use tonic::{Code, Status};
+
+  use super::*;
+  use crate::store::model::HealthStatus;
+
+  const NAMESPACE: &str = "default";
+
+  fn hub(id: &str, version: &str) -> GSFInstance {
+    GSFInstance {
+      id: id.to_string(),
+      instance_type: InstanceType::Hub,
+      name: id.to_string(),
+      version: version.to_string(),
+      host: "10.0.0.1".to_string(),
+      port: "50051".to_string(),
+      repositories: "".to_string(),
+      relay_host: "relay.example.com".to_string(),
+      relay_port: "443".to_string(),
+      health: GSFHealth::default(),
+      relay_status: RelayStatus::default(),
+      labels: BTreeMap::new(),
+      resource_version: 0,
+    }
+  }
+
+  async fn resource_version(store: &InMemoryStore, id: &str) -> u64 {
+    store.get_instance(NAMESPACE, InstanceType::Hub, id).await.unwrap().unwrap().resource_version
   }
 
-  fn update_server(&self, gsfserver: GSFServer) {
-    let mut servers = self.servers.lock().unwrap();
-    servers.insert(gsfserver.id.clone(), gsfserver);
+  #[tokio::test]
+  async fn rejects_registrations_at_another_version() {
+    let store = InMemoryStore::new();
+    let error = store.add_instance(NAMESPACE, hub("hub-1", "0.1"), Some(1)).await.unwrap_err();
+    assert!(matches!(error, StoreError::Conflict(_)), "an unregistered instance is at no version: {}", error);
+
+    let first = store.add_instance(NAMESPACE, hub("hub-1", "0.1"), None).await.unwrap();
+    let second = store.add_instance(NAMESPACE, hub("hub-1", "0.2"), Some(first)).await.unwrap();
+    assert!(second > first);
+
+    let error = store.add_instance(NAMESPACE, hub("hub-1", "0.3"), Some(first)).await.unwrap_err();
+    assert!(matches!(error, StoreError::Conflict(_)));
+    assert_eq!(Status::from(error).code(), Code::Aborted);
+    assert_eq!(store.get_instance(NAMESPACE, InstanceType::Hub, "hub-1").await.unwrap().unwrap().version, "0.2");
+  }
+
+  #[tokio::test]
+  async fn resource_versions_increase_with_every_change() {
+    let store = InMemoryStore::new();
+    let first = store.add_instance(NAMESPACE, hub("hub-1", "0.1"), None).await.unwrap();
+    assert_eq!(store.revision().await.unwrap(), first);
+
+    // re-registering an unchanged instance is not a change
+    assert_eq!(store.add_instance(NAMESPACE, hub("hub-1", "0.1"), None).await.unwrap(), first);
+    assert_eq!(store.revision().await.unwrap(), first);
+
+    let second = store.add_instance(NAMESPACE, hub("hub-1", "0.2"), None).await.unwrap();
+    assert!(second > first);
+    assert_eq!(resource_version(&store, "hub-1").await, second);
+
+    // a health transition is published, a new latency is not, neither changes the registration
+    let healthy = GSFHealth { status: HealthStatus::Healthy, latency_ms: 5, ..GSFHealth::default() };
+    store.set_health(NAMESPACE, InstanceType::Hub, "hub-1", second, healthy.clone()).await.unwrap();
+    let after_health = store.revision().await.unwrap();
+    assert!(after_health > second);
+    store.set_health(NAMESPACE, InstanceType::Hub, "hub-1", second, GSFHealth { latency_ms: 7, ..healthy }).await.unwrap();
+    assert_eq!(store.revision().await.unwrap(), after_health);
+
+    store.set_relay_status(NAMESPACE, InstanceType::Hub, "hub-1", second, RelayStatus::Reachable).await.unwrap();
+    let after_relay = store.revision().await.unwrap();
+    assert!(after_relay > after_health);
+    assert_eq!(resource_version(&store, "hub-1").await, second);
+
+    let third = store.add_instance(NAMESPACE, hub("hub-1", "0.3"), None).await.unwrap();
+    assert!(third > after_relay);
+    // the registration keeps its probe results, as the endpoint did not change
+    let registered = store.get_instance(NAMESPACE, InstanceType::Hub, "hub-1").await.unwrap().unwrap();
+    assert_eq!((registered.health.status, registered.relay_status), (HealthStatus::Healthy, RelayStatus::Reachable));
   }
 
-  fn remove_server(&self, id: String) {
-    let mut servers = self.servers.lock().unwrap();
-    servers.remove(&id);
+  #[tokio::test]
+  async fn drops_probe_results_of_replaced_registrations() {
+    let store = InMemoryStore::new();
+    let probed = store.add_instance(NAMESPACE, hub("hub-1", "0.1"), None).await.unwrap();
+    let mut moved = hub("hub-1", "0.1");
+    moved.host = "10.0.0.2".to_string();
+    store.add_instance(NAMESPACE, moved, None).await.unwrap();
+
+    let unhealthy = GSFHealth { status: HealthStatus::Unhealthy, ..GSFHealth::default() };
+    store.set_health(NAMESPACE, InstanceType::Hub, "hub-1", probed, unhealthy).await.unwrap();
+    store.set_relay_status(NAMESPACE, InstanceType::Hub, "hub-1", probed, RelayStatus::Unreachable).await.unwrap();
+    let registered = store.get_instance(NAMESPACE, InstanceType::Hub, "hub-1").await.unwrap().unwrap();
+    assert_eq!((registered.health.status, registered.relay_status), (HealthStatus::Unknown, RelayStatus::Unknown));
   }
 
-}
\ No newline at end of file
+  #[tokio::test]
+  async fn keeps_the_last_events_to_resume_from() {
+    let store = InMemoryStore::new();
+    assert_eq!(store.events_since(0).await.unwrap().unwrap().len(), 0);
+    for version in 1..=EVENT_HISTORY_SIZE + 5 {
+      store.add_instance(NAMESPACE, hub("hub-1", &version.to_string()), None).await.unwrap();
+    }
+    let revision = store.revision().await.unwrap();
+    assert_eq!(revision, (EVENT_HISTORY_SIZE + 5) as u64);
+
+    assert_eq!(store.events_since(revision).await.unwrap().unwrap().len(), 0);
+    let missed = store.events_since(revision - 2).await.unwrap().unwrap();
+    assert_eq!(missed.iter().map(|event| event.revision).collect::<Vec<_>>(), vec![revision - 1, revision]);
+    // the oldest kept event directly follows the revision
+    let oldest = revision - EVENT_HISTORY_SIZE as u64;
+    assert_eq!(store.events_since(oldest).await.unwrap().unwrap().len(), EVENT_HISTORY_SIZE);
+    // events before that are gone, the caller resets
+    assert!(store.events_since(oldest - 1).await.unwrap().is_none());
+    assert!(store.events_since(0).await.unwrap().is_none());
+    // revisions the Store never had are unknown as well
+    assert!(store.events_since(revision + 1).await.unwrap().is_none());
+  }
+}
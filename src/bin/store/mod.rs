@@ -1 +1,6 @@
-pub mod inmemory;
\ No newline at end of file
+pub mod backend;
+pub mod inmemory;
+pub mod events;
+pub mod model;
+pub mod selector;
+pub mod watch;
\ No newline at end of file
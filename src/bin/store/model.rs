@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+  /// not probed yet
+  #[default]
+  Unknown,
+  Healthy,
+  Unhealthy,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct GSFHealth {
+  pub status: HealthStatus,
+  pub latency_ms: u64,
+  pub last_error: String,
+  /// unix timestamp (seconds) of the last probe
+  pub last_checked: u64,
+  pub consecutive_failures: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RelayStatus {
+  /// not probed yet
+  #[default]
+  Unknown,
+  Reachable,
+  Unreachable,
+  /// the hub did not register a (valid) relay endpoint
+  Unset,
+}
+
+/// The kinds of Gitstafette instances, as in `gitstafette_info.InstanceType`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InstanceType {
+  Relay,
+  Server,
+  Client,
+  Hub,
+  Discovery,
+  Explorer,
+}
+
+impl InstanceType {
+  pub fn name(&self) -> &'static str {
+    match self {
+      InstanceType::Relay => "relay",
+      InstanceType::Server => "server",
+      InstanceType::Client => "client",
+      InstanceType::Hub => "hub",
+      InstanceType::Discovery => "discovery",
+      InstanceType::Explorer => "explorer",
+    }
+  }
+}
+
+/// A registered instance, ids are unique per instance type.
+/// Only hubs have a relay, which is probed along with the hub.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GSFInstance {
+  pub id: String,
+  pub instance_type: InstanceType,
+  pub name: String,
+  pub version: String,
+  pub host: String,
+  pub port: String,
+  pub repositories: String,
+  pub relay_host: String,
+  pub relay_port: String,
+  pub health: GSFHealth,
+  pub relay_status: RelayStatus,
+  /// e.g. `env=prod`, set by the instance when it registers
+  pub labels: BTreeMap<String, String>,
+  /// the Store revision of the last change of the registration, probe results do not change it, although health transitions are published as updates
+  pub resource_version: u64,
+}
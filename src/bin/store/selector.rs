@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+
+/// A Kubernetes-style label selector, such as `env=prod,zone in (a,b),!canary`.
+/// An instance matches when it matches every requirement, the empty selector matches every instance.
+/// See https://kubernetes.io/docs/concepts/overview/working-with-objects/labels/#label-selectors
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LabelSelector {
+  requirements: Vec<Requirement>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Requirement {
+  /// `key=value` or `key==value`
+  Equals(String, String),
+  /// `key!=value`, also matches instances without the label
+  NotEquals(String, String),
+  /// `key in (a,b)`
+  In(String, Vec<String>),
+  /// `key notin (a,b)`, also matches instances without the label
+  NotIn(String, Vec<String>),
+  /// `key`
+  Exists(String),
+  /// `!key`
+  DoesNotExist(String),
+}
+
+impl LabelSelector {
+  pub fn parse(selector: &str) -> Result<LabelSelector, String> {
+    let mut requirements = Vec::new();
+    if selector.trim().is_empty() {
+      return Ok(LabelSelector { requirements });
+    }
+    for requirement in split_requirements(selector)? {
+      requirements.push(parse_requirement(requirement.trim())?);
+    }
+    Ok(LabelSelector { requirements })
+  }
+
+  pub fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+    self.requirements.iter().all(|requirement| match requirement {
+      Requirement::Equals(key, value) => labels.get(key) == Some(value),
+      Requirement::NotEquals(key, value) => labels.get(key) != Some(value),
+      Requirement::In(key, values) => labels.get(key).is_some_and(|label| values.contains(label)),
+      Requirement::NotIn(key, values) => !labels.get(key).is_some_and(|label| values.contains(label)),
+      Requirement::Exists(key) => labels.contains_key(key),
+      Requirement::DoesNotExist(key) => !labels.contains_key(key),
+    })
+  }
+}
+
+/// the requirements are separated by commas, except for the commas between the parentheses of a set
+fn split_requirements(selector: &str) -> Result<Vec<&str>, String> {
+  let mut requirements = Vec::new();
+  let mut in_set = false;
+  let mut start = 0;
+  for (index, character) in selector.char_indices() {
+    match character {
+      '(' if in_set => return Err(format!("nested parentheses in label selector {}", selector)),
+      '(' => in_set = true,
+      ')' if !in_set => return Err(format!("unbalanced parentheses in label selector {}", selector)),
+      ')' => in_set = false,
+      ',' if !in_set => {
+        requirements.push(&selector[start..index]);
+        start = index + 1;
+      }
+      _ => {}
+    }
+  }
+  if in_set {
+    return Err(format!("unbalanced parentheses in label selector {}", selector));
+  }
+  requirements.push(&selector[start..]);
+  Ok(requirements)
+}
+
+fn parse_requirement(requirement: &str) -> Result<Requirement, String> {
+  if requirement.is_empty() {
+    return Err("empty requirement in label selector".to_string());
+  }
+  // a set is parsed first, so that an operator between its parentheses is reported as an invalid value
+  if let Some(open) = requirement.find('(') {
+    let mut words = requirement[..open].split_whitespace();
+    let (Some(key), Some(operator), None) = (words.next(), words.next(), words.next()) else {
+      return Err(format!("invalid requirement {} in label selector", requirement));
+    };
+    let Some(values) = requirement[open + 1..].strip_suffix(')') else {
+      return Err(format!("invalid requirement {} in label selector", requirement));
+    };
+    if values.trim().is_empty() {
+      return Err(format!("empty set in requirement {} in label selector", requirement));
+    }
+    let values = values.split(',').map(set_value).collect::<Result<Vec<_>, _>>()?;
+    return match operator {
+      "in" => Ok(Requirement::In(key_name(key)?, values)),
+      "notin" => Ok(Requirement::NotIn(key_name(key)?, values)),
+      _ => Err(format!("unknown operator {} in label selector", operator)),
+    };
+  }
+  if let Some((key, value)) = requirement.split_once("!=") {
+    return Ok(Requirement::NotEquals(key_name(key)?, label_value(value)?));
+  }
+  if let Some((key, value)) = requirement.split_once("==").or_else(|| requirement.split_once('=')) {
+    return Ok(Requirement::Equals(key_name(key)?, label_value(value)?));
+  }
+  if let Some(key) = requirement.strip_prefix('!') {
+    return Ok(Requirement::DoesNotExist(key_name(key)?));
+  }
+  Ok(Requirement::Exists(key_name(requirement)?))
+}
+
+fn key_name(key: &str) -> Result<String, String> {
+  let key = key.trim();
+  if key.is_empty() || !key.chars().all(label_character) {
+    return Err(format!("invalid label key '{}' in label selector", key));
+  }
+  Ok(key.to_string())
+}
+
+/// the value after `=`, `==` or `!=`, which may be empty like in Kubernetes, but not contain another operator or whitespace
+fn label_value(value: &str) -> Result<String, String> {
+  let value = value.trim();
+  if !value.chars().all(label_character) {
+    return Err(format!("invalid label value '{}' in label selector", value));
+  }
+  Ok(value.to_string())
+}
+
+/// a value between the parentheses of a set, which may not be empty
+fn set_value(value: &str) -> Result<String, String> {
+  let value = label_value(value)?;
+  if value.is_empty() {
+    return Err("empty value in set in label selector".to_string());
+  }
+  Ok(value)
+}
+
+fn label_character(character: char) -> bool {
+  character.is_ascii_alphanumeric() || matches!(character, '-' | '_' | '.' | '/')
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn labels(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+  }
+
+  fn requirements(selector: &str) -> Vec<Requirement> {
+    LabelSelector::parse(selector).unwrap().requirements
+  }
+
+  #[test]
+  fn parses_every_operator() {
+    assert_eq!(requirements("env=prod, tier == web,zone!=b"), vec![
+      Requirement::Equals("env".to_string(), "prod".to_string()),
+      Requirement::Equals("tier".to_string(), "web".to_string()),
+      Requirement::NotEquals("zone".to_string(), "b".to_string()),
+    ]);
+    assert_eq!(requirements("zone in (a, b),tier notin (web),canary,!legacy"), vec![
+      Requirement::In("zone".to_string(), vec!["a".to_string(), "b".to_string()]),
+      Requirement::NotIn("tier".to_string(), vec!["web".to_string()]),
+      Requirement::Exists("canary".to_string()),
+      Requirement::DoesNotExist("legacy".to_string()),
+    ]);
+    assert_eq!(requirements("env="), vec![Requirement::Equals("env".to_string(), String::new())]);
+    assert_eq!(requirements("  "), vec![]);
+  }
+
+  #[test]
+  fn rejects_invalid_selectors() {
+    for selector in [
+      "zone in ()", "zone in ( )", "zone in (a,)", "zone in (a,,b)", "zone in (a", "zone in a)", "zone in ((a))",
+      "zone (a)", "zone is (a)", "zone in (a=b)", "a=b=c", "a==b=c", "a!=b=c", "a!=b!=c", "a=b c", "a b=c",
+      "=b", "!=b", "!", "env=prod,", ",env=prod", "env=prod,,tier=web", "!a=b",
+    ] {
+      assert!(LabelSelector::parse(selector).is_err(), "{} should not parse", selector);
+    }
+  }
+
+  #[test]
+  fn matches_labels() {
+    let prod = labels(&[("env", "prod"), ("zone", "a")]);
+    let dev = labels(&[("env", "dev")]);
+    let none = labels(&[]);
+    let matching = |selector: &str| {
+      let selector = LabelSelector::parse(selector).unwrap();
+      [&prod, &dev, &none].map(|labels| selector.matches(labels))
+    };
+    assert_eq!(matching(""), [true, true, true]);
+    assert_eq!(matching("env=prod"), [true, false, false]);
+    assert_eq!(matching("env!=prod"), [false, true, true]);
+    assert_eq!(matching("zone in (a,b)"), [true, false, false]);
+    assert_eq!(matching("zone notin (a,b)"), [false, true, true]);
+    assert_eq!(matching("zone"), [true, false, false]);
+    assert_eq!(matching("!zone"), [false, true, true]);
+    assert_eq!(matching("env in (prod,dev),!zone"), [false, true, false]);
+  }
+}
@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Instant;
+
+use crate::store::backend::{Store, StoreResult};
+use crate::store::events::StoreEvent;
+use crate::store::model::InstanceType;
+
+/// Waits until an instance of the type in the namespace changed after `index`, or until the timeout expires,
+/// and returns the revision of the Store to list the instances at.
+/// Returns right away when the changes since `index` are no longer known, so the caller lists again.
+/// Instances becoming healthy or unhealthy, and relay status changes, end the wait like registrations do.
+pub async fn wait_for_change<S: Store>(store: &S, namespace: &str, instance_type: InstanceType, index: u64, timeout: Duration) -> StoreResult<u64> {
+  let changed = |event: &StoreEvent| event.revision > index && event.namespace == namespace && event.instance == instance_type;
+  // subscribe before reading the history, so no change falls in between
+  let mut events = store.subscribe();
+  match store.events_since(index).await? {
+    Some(missed_events) if !missed_events.iter().any(changed) => {}
+    _ => return store.revision().await,
+  }
+
+  let deadline = Instant::now() + timeout;
+  loop {
+    match tokio::time::timeout_at(deadline, events.recv()).await {
+      Ok(Ok(event)) if changed(&event) => break,
+      Ok(Ok(_)) => continue,
+      // missed events may include a change, the caller lists again
+      Ok(Err(RecvError::Lagged(_))) | Ok(Err(RecvError::Closed)) => break,
+      Err(_) => break,
+    }
+  }
+  store.revision().await
+}
+
+/// The events of the namespace since `revision`, and the revision to ask for the next changes from.
+/// The events of other namespaces are skipped, but count for the revision.
+/// Returns None with the current revision when the changes are no longer known, the caller then lists everything again.
+pub async fn changes_since<S: Store>(store: &S, namespace: &str, revision: u64) -> StoreResult<(u64, Option<Vec<StoreEvent>>)> {
+  let Some(events) = store.events_since(revision).await? else {
+    return Ok((store.revision().await?, None));
+  };
+  let next_revision = events.last().map(|event| event.revision).unwrap_or(revision);
+  let changes = events.into_iter().filter(|event| event.namespace == namespace).collect();
+  Ok((next_revision, Some(changes)))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::BTreeMap;
+
+  use super::*;
+  use crate::store::inmemory::InMemoryStore;
+  use crate::store::model::{GSFHealth, GSFInstance, HealthStatus, RelayStatus};
+
+  const NAMESPACE: &str = "default";
+  const TIMEOUT: Duration = Duration::from_millis(200);
+
+  fn instance(id: &str, instance_type: InstanceType) -> GSFInstance {
+    GSFInstance {
+      id: id.to_string(),
+      instance_type,
+      name: id.to_string(),
+      version: "0.1".to_string(),
+      host: "10.0.0.1".to_string(),
+      port: "50051".to_string(),
+      repositories: "".to_string(),
+      relay_host: "".to_string(),
+      relay_port: "".to_string(),
+      health: GSFHealth::default(),
+      relay_status: RelayStatus::default(),
+      labels: BTreeMap::new(),
+      resource_version: 0,
+    }
+  }
+
+  #[tokio::test]
+  async fn lists_the_changes_of_the_namespace() {
+    let store = InMemoryStore::new();
+    let start = store.add_instance(NAMESPACE, instance("hub-1", InstanceType::Hub), None).await.unwrap();
+    store.add_instance("other", instance("hub-2", InstanceType::Hub), None).await.unwrap();
+    store.add_instance(NAMESPACE, instance("server-1", InstanceType::Server), None).await.unwrap();
+    let last = store.add_instance("other", instance("hub-3", InstanceType::Hub), None).await.unwrap();
+
+    let (revision, changes) = changes_since(&store, NAMESPACE, start).await.unwrap();
+    let changes = changes.unwrap();
+    assert_eq!(changes.iter().map(|event| event.id.as_str()).collect::<Vec<_>>(), vec!["server-1"]);
+    // the changes of the other namespace count for the revision, so they are not asked for again
+    assert_eq!(revision, last);
+
+    let (revision, changes) = changes_since(&store, NAMESPACE, last).await.unwrap();
+    assert_eq!((revision, changes.unwrap().len()), (last, 0));
+  }
+
+  #[tokio::test]
+  async fn resets_when_the_changes_are_unknown() {
+    let store = InMemoryStore::new();
+    let last = store.add_instance(NAMESPACE, instance("hub-1", InstanceType::Hub), None).await.unwrap();
+    let (revision, changes) = changes_since(&store, NAMESPACE, last + 10).await.unwrap();
+    assert_eq!(revision, last);
+    assert!(changes.is_none());
+  }
+
+  #[tokio::test]
+  async fn returns_missed_changes_right_away() {
+    let store = InMemoryStore::new();
+    let index = store.revision().await.unwrap();
+    let last = store.add_instance(NAMESPACE, instance("hub-1", InstanceType::Hub), None).await.unwrap();
+    let started = Instant::now();
+    assert_eq!(wait_for_change(&store, NAMESPACE, InstanceType::Hub, index, TIMEOUT).await.unwrap(), last);
+    assert!(started.elapsed() < TIMEOUT);
+  }
+
+  #[tokio::test]
+  async fn returns_on_a_change() {
+    let store = InMemoryStore::new();
+    let registered = store.add_instance(NAMESPACE, instance("hub-1", InstanceType::Hub), None).await.unwrap();
+    let writer = store.clone();
+    tokio::spawn(async move {
+      tokio::time::sleep(Duration::from_millis(20)).await;
+      let healthy = GSFHealth { status: HealthStatus::Healthy, ..GSFHealth::default() };
+      writer.set_health(NAMESPACE, InstanceType::Hub, "hub-1", registered, healthy).await.unwrap();
+    });
+
+    let started = Instant::now();
+    let revision = wait_for_change(&store, NAMESPACE, InstanceType::Hub, registered, Duration::from_secs(10)).await.unwrap();
+    assert!(revision > registered);
+    assert!(started.elapsed() < Duration::from_secs(10));
+  }
+
+  #[tokio::test]
+  async fn returns_on_timeout_without_a_change() {
+    let store = InMemoryStore::new();
+    let index = store.add_instance(NAMESPACE, instance("hub-1", InstanceType::Hub), None).await.unwrap();
+    let writer = store.clone();
+    tokio::spawn(async move {
+      // neither is a change of the hubs in the namespace
+      writer.add_instance("other", instance("hub-2", InstanceType::Hub), None).await.unwrap();
+      writer.add_instance(NAMESPACE, instance("server-1", InstanceType::Server), None).await.unwrap();
+    });
+
+    let started = Instant::now();
+    let revision = wait_for_change(&store, NAMESPACE, InstanceType::Hub, index, TIMEOUT).await.unwrap();
+    assert!(started.elapsed() >= TIMEOUT);
+    assert_eq!(revision, store.revision().await.unwrap());
+  }
+}
@@ -0,0 +1,204 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use autometrics::autometrics;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tokio::sync::{broadcast, Notify};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::retry::backoff::Backoff;
+use crate::store::events::StoreEvent;
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+pub const SIGNATURE_HEADER: &str = "X-Gitstafette-Signature";
+pub const EVENT_HEADER: &str = "X-Gitstafette-Event";
+
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+  pub urls: Vec<String>,
+  pub secret: Option<String>,
+  pub queue_size: usize,
+  pub max_retries: u32,
+  pub timeout: Duration,
+}
+
+#[derive(Debug, Clone)]
+struct WebhookDelivery {
+  event_name: String,
+  body: Vec<u8>,
+}
+
+/// Bounded queue of deliveries for a single webhook receiver.
+/// When full, the oldest delivery is dropped, so a slow receiver never blocks the Store.
+#[derive(Debug)]
+struct WebhookQueue {
+  deliveries: Mutex<VecDeque<WebhookDelivery>>,
+  capacity: usize,
+  notify: Notify,
+}
+
+impl WebhookQueue {
+  fn new(capacity: usize) -> Self {
+    WebhookQueue {
+      deliveries: Mutex::new(VecDeque::with_capacity(capacity)),
+      capacity,
+      notify: Notify::new(),
+    }
+  }
+
+  fn push(&self, url: &str, delivery: WebhookDelivery) {
+    {
+      let mut deliveries = self.deliveries.lock().unwrap();
+      if deliveries.len() >= self.capacity {
+        if let Some(dropped) = deliveries.pop_front() {
+          println!("Webhook queue for {} is full, dropping event: {}", url, dropped.event_name);
+        }
+      }
+      deliveries.push_back(delivery);
+    }
+    self.notify.notify_one();
+  }
+
+  async fn pop(&self) -> WebhookDelivery {
+    loop {
+      if let Some(delivery) = self.deliveries.lock().unwrap().pop_front() {
+        return delivery;
+      }
+      self.notify.notified().await;
+    }
+  }
+}
+
+/// Starts posting every Store event to the configured webhooks.
+/// Each webhook gets its own queue and worker, so one slow receiver does not delay the others.
+pub fn start_webhook_dispatcher(config: WebhookConfig, mut events: broadcast::Receiver<StoreEvent>) {
+  let client = reqwest::Client::builder()
+    .timeout(config.timeout)
+    .build()
+    .expect("Unable to create webhook HTTP client");
+
+  let mut queues = Vec::new();
+  for url in &config.urls {
+    println!("Webhook configured for {}", url);
+    let queue = Arc::new(WebhookQueue::new(config.queue_size));
+    queues.push((url.to_string(), queue.clone()));
+
+    let worker_client = client.clone();
+    let worker_url = url.to_string();
+    let secret = config.secret.clone();
+    let max_retries = config.max_retries;
+    tokio::spawn(async move {
+      let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+      loop {
+        let delivery = queue.pop().await;
+        deliver_with_retries(&worker_client, &worker_url, secret.as_deref(), max_retries, &mut backoff, &delivery).await;
+      }
+    });
+  }
+
+  tokio::spawn(async move {
+    loop {
+      match events.recv().await {
+        Ok(event) => {
+          let body = match serde_json::to_vec(&event) {
+            Ok(body) => body,
+            Err(error) => {
+              println!("Unable to serialize webhook event: {:?}", error);
+              continue;
+            }
+          };
+          let delivery = WebhookDelivery { event_name: event.name(), body };
+          for (url, queue) in &queues {
+            queue.push(url, delivery.clone());
+          }
+        }
+        Err(RecvError::Lagged(missed)) => {
+          println!("Webhook dispatcher lagging behind, missed {} events", missed);
+        }
+        Err(RecvError::Closed) => {
+          println!("Store event channel closed, stopping webhook dispatcher");
+          return;
+        }
+      }
+    }
+  });
+}
+
+/// Retries with the jittered backoff of the worker, so webhooks that failed together do not retry together
+async fn deliver_with_retries(client: &reqwest::Client, url: &str, secret: Option<&str>, max_retries: u32, backoff: &mut Backoff, delivery: &WebhookDelivery) {
+  backoff.reset();
+  for attempt in 0..=max_retries {
+    match deliver(client, url, secret, delivery).await {
+      Ok(()) => return,
+      Err(WebhookError::Rejected(status)) => {
+        println!("Webhook {} rejected event {} with status {}, not retrying", url, delivery.event_name, status);
+        return;
+      }
+      Err(error) => {
+        println!("Webhook {} failed to receive event {} (attempt {}): {}", url, delivery.event_name, attempt + 1, error);
+      }
+    }
+    if attempt < max_retries {
+      tokio::time::sleep(backoff.next_delay()).await;
+    }
+  }
+  println!("Giving up on delivering event {} to webhook {}", delivery.event_name, url);
+}
+
+#[derive(Debug)]
+pub enum WebhookError {
+  /// the receiver could not be reached or did not answer in time
+  Transport(reqwest::Error),
+  /// the receiver answered with a status that is worth retrying (5xx, 408, 429)
+  Unavailable(reqwest::StatusCode),
+  /// the receiver refused the event, retrying will not help
+  Rejected(reqwest::StatusCode),
+}
+
+impl std::fmt::Display for WebhookError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      WebhookError::Transport(error) => write!(f, "transport error: {}", error),
+      WebhookError::Unavailable(status) => write!(f, "receiver unavailable: {}", status),
+      WebhookError::Rejected(status) => write!(f, "rejected: {}", status),
+    }
+  }
+}
+
+#[autometrics]
+async fn deliver(client: &reqwest::Client, url: &str, secret: Option<&str>, delivery: &WebhookDelivery) -> Result<(), WebhookError> {
+  let mut request = client
+    .post(url)
+    .header(reqwest::header::CONTENT_TYPE, "application/json")
+    .header(EVENT_HEADER, delivery.event_name.as_str());
+  if let Some(secret) = secret {
+    request = request.header(SIGNATURE_HEADER, sign(secret, &delivery.body));
+  }
+
+  let response = request
+    .body(delivery.body.clone())
+    .send()
+    .await
+    .map_err(WebhookError::Transport)?;
+
+  let status = response.status();
+  if status.is_success() {
+    Ok(())
+  } else if status.is_server_error() || status == reqwest::StatusCode::REQUEST_TIMEOUT || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+    Err(WebhookError::Unavailable(status))
+  } else {
+    Err(WebhookError::Rejected(status))
+  }
+}
+
+/// Signs the payload with HMAC-SHA256, formatted as `sha256=<hex digest>`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+  let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+    .expect("HMAC accepts keys of any size");
+  mac.update(body);
+  format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
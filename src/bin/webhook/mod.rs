@@ -0,0 +1 @@
+pub mod dispatcher;
\ No newline at end of file
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::task::{Context as TaskContext, Poll};
+use std::time::Duration;
+
+use opentelemetry::Context;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::transport::{Channel, Endpoint};
+use tower::discover::Change;
+
+use crate::client::config::TlsConfig;
+use crate::client::discovery::{tls_config, DiscoveryClient};
+use crate::client::error::ClientError;
+use crate::proto::gitstafette_discovery::{GitstafetteHub, GitstafetteServer};
+
+// changes buffered between the polling task and the balancer
+const CHANGES_CAPACITY: usize = 64;
+
+/// Which registrations become endpoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstanceKind {
+    Hubs,
+    Servers,
+}
+
+/// Restricts the endpoints to the instances that match every set field
+#[derive(Debug, Clone, Default)]
+pub struct InstanceFilter {
+    /// only instances that serve this repository, one of their comma separated repositories
+    pub repository: Option<String>,
+    /// only instances with labels that match this selector, e.g. `env=prod,zone in (a,b),!canary`,
+    /// evaluated by the Discovery Server
+    pub label_selector: Option<String>,
+}
+
+impl InstanceFilter {
+    fn matches(&self, repositories: &str) -> bool {
+        match &self.repository {
+            Some(repository) => repositories.split(',').any(|served| served.trim() == repository),
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BalanceConfig {
+    pub kind: InstanceKind,
+    pub filter: InstanceFilter,
+    /// how often the registrations are polled
+    pub refresh_interval: Duration,
+    /// `http` or `https`, the registrations only have a host and port
+    pub protocol: String,
+    /// for `https` instances
+    pub tls: TlsConfig,
+    pub connect_timeout: Duration,
+    /// also balance over instances that failed their health probes
+    pub include_unhealthy: bool,
+}
+
+impl BalanceConfig {
+    pub fn new(kind: InstanceKind) -> Self {
+        BalanceConfig {
+            kind,
+            filter: InstanceFilter::default(),
+            refresh_interval: Duration::from_secs(10),
+            protocol: "http".to_string(),
+            tls: TlsConfig::default(),
+            connect_timeout: Duration::from_secs(5),
+            include_unhealthy: false,
+        }
+    }
+}
+
+/// The registered hubs or servers as a `tower::discover::Discover`, keyed by their id.
+///
+/// Polls the Discovery Server and yields an insert for every new or moved instance, and a remove for every
+/// instance that is no longer registered or no longer matches the filter. While the Discovery Server is
+/// unavailable the endpoints are left as they are. Polling stops when this is dropped.
+#[derive(Debug)]
+pub struct DiscoveredEndpoints {
+    changes: ReceiverStream<Change<String, Endpoint>>,
+    poller: JoinHandle<()>,
+}
+
+impl DiscoveredEndpoints {
+    pub fn new(client: DiscoveryClient, config: BalanceConfig, cx: &Context) -> Self {
+        let (sender, receiver) = mpsc::channel(CHANGES_CAPACITY);
+        let cx = cx.clone();
+        let poller = tokio::spawn(async move { poll_changes(client, config, sender, cx).await });
+        DiscoveredEndpoints {
+            changes: ReceiverStream::new(receiver),
+            poller,
+        }
+    }
+}
+
+impl Stream for DiscoveredEndpoints {
+    type Item = Result<Change<String, Endpoint>, Infallible>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.changes).poll_next(cx).map(|change| change.map(Ok))
+    }
+}
+
+impl Drop for DiscoveredEndpoints {
+    fn drop(&mut self) {
+        self.poller.abort();
+    }
+}
+
+/// A channel that balances requests over the registered hubs or servers, following their registrations.
+/// Requests fail until the first instance is discovered.
+pub fn balance_channel(client: DiscoveryClient, config: BalanceConfig, cx: &Context) -> Channel {
+    let (channel, sender) = Channel::balance_channel(CHANGES_CAPACITY);
+    let mut discovered = DiscoveredEndpoints::new(client, config, cx);
+    // dropping `discovered` when the channel is dropped stops the polling, also while nothing changes
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = sender.closed() => break,
+                change = discovered.next() => {
+                    let Some(Ok(change)) = change else {
+                        break;
+                    };
+                    if sender.send(change).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+    channel
+}
+
+async fn poll_changes(client: DiscoveryClient, config: BalanceConfig, sender: mpsc::Sender<Change<String, Endpoint>>, cx: Context) {
+    // the address every discovered id was inserted with
+    let mut current: HashMap<String, String> = HashMap::new();
+    loop {
+        match instances(&client, &config, &cx).await {
+            Ok(instances) => {
+                let mut changes = Vec::new();
+                current.retain(|id, _| {
+                    let registered = instances.contains_key(id);
+                    if !registered {
+                        changes.push(Change::Remove(id.to_string()));
+                    }
+                    registered
+                });
+                for (id, address) in instances {
+                    if current.get(&id) == Some(&address) {
+                        continue;
+                    }
+                    match endpoint(&address, &config) {
+                        Ok(endpoint) => {
+                            changes.push(Change::Insert(id.to_string(), endpoint));
+                            current.insert(id, address);
+                        }
+                        Err(error) => tracing::warn!("Skipping {} with invalid address {}: {}", id, address, error),
+                    }
+                }
+                for change in changes {
+                    if sender.send(change).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            Err(error) => tracing::warn!("Failed to discover {:?}, keeping {} endpoints: {}", config.kind, current.len(), error),
+        }
+        tokio::time::sleep(config.refresh_interval).await;
+    }
+}
+
+/// The addresses of the registered instances that match the filter, by id
+async fn instances(client: &DiscoveryClient, config: &BalanceConfig, cx: &Context) -> Result<HashMap<String, String>, ClientError> {
+    let address = |host: &str, port: &str| format!("{}://{}:{}", config.protocol, host, port);
+    let label_selector = config.filter.label_selector.as_deref().unwrap_or_default();
+    let instances = match config.kind {
+        InstanceKind::Hubs => client
+            .get_hubs_matching(label_selector, config.include_unhealthy, cx)
+            .await?
+            .into_iter()
+            .filter(|hub: &GitstafetteHub| config.filter.matches(&hub.repositories))
+            .map(|hub| (hub.id, address(&hub.host, &hub.port)))
+            .collect(),
+        InstanceKind::Servers => client
+            .get_servers_matching(label_selector, config.include_unhealthy, cx)
+            .await?
+            .into_iter()
+            .filter(|server: &GitstafetteServer| config.filter.matches(&server.repositories))
+            .map(|server| (server.id, address(&server.host, &server.port)))
+            .collect(),
+    };
+    Ok(instances)
+}
+
+fn endpoint(address: &str, config: &BalanceConfig) -> Result<Endpoint, tonic::transport::Error> {
+    let endpoint = Endpoint::from_shared(address.to_string())?.connect_timeout(config.connect_timeout);
+    if endpoint.uri().scheme_str() == Some("https") {
+        endpoint.tls_config(tls_config(&config.tls))
+    } else {
+        Ok(endpoint)
+    }
+}
\ No newline at end of file
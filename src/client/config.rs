@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+/// TLS settings for `https` endpoints, without them the system roots verify the server
+#[derive(Debug, Clone, Default)]
+pub struct TlsConfig {
+    /// PEM encoded CA certificate to verify the server with, instead of the system roots
+    pub ca_certificate: Option<Vec<u8>>,
+    /// name to verify the server certificate against, instead of the host of the endpoint
+    pub domain: Option<String>,
+    /// PEM encoded client certificate and key, for mutual TLS
+    pub identity: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// Discovery Server addresses as `protocol://host:port`, calls fail over between them in order
+    pub endpoints: Vec<String>,
+    pub tls: TlsConfig,
+    /// sent as bearer token in the `authorization` metadata of every request
+    pub token: Option<String>,
+    /// namespace of the registrations and lookups, the `default` namespace when None
+    pub namespace: Option<String>,
+    pub connect_timeout: Duration,
+    /// timeout of every request, none by default
+    pub timeout: Option<Duration>,
+    /// attempts after the first one for calls that failed because the Discovery Server was unavailable, timeouts are not retried
+    pub retries: u32,
+    /// delay before the first retry, doubling with every retry
+    pub retry_backoff: Duration,
+    /// register with, and deregister from, every Discovery Server instead of a single healthy one
+    pub register_all: bool,
+}
+
+impl ClientConfig {
+    pub fn new(endpoints: Vec<String>) -> Self {
+        ClientConfig {
+            endpoints,
+            tls: TlsConfig::default(),
+            token: None,
+            namespace: None,
+            connect_timeout: Duration::from_secs(5),
+            timeout: None,
+            retries: 2,
+            retry_backoff: Duration::from_millis(200),
+            register_all: false,
+        }
+    }
+}
\ No newline at end of file
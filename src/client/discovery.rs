@@ -0,0 +1,761 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use opentelemetry::{global, Context};
+use tonic::metadata::MetadataValue;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity};
+use tonic_health::pb::health_check_response::ServingStatus;
+use tonic_health::pb::health_client::HealthClient;
+use tonic_health::pb::HealthCheckRequest;
+
+use crate::client::config::{ClientConfig, TlsConfig};
+use crate::client::error::ClientError;
+use crate::client::propagation::MetadataMap;
+use crate::proto::gitstafette_discovery::discovery_client::DiscoveryClient as GrpcClient;
+use crate::proto::gitstafette_discovery::{
+    DeregisterHubRequest, DeregisterInstanceRequest, DeregisterServerRequest, FleetInstanceInfo, GetChangesRequest, GetChangesResponse,
+    GetFleetInfoRequest, GetHubsRequest, GetHubsResponse,
+    GetServersRequest, GetServersResponse, GitstafetteHub, GitstafetteInstance, GitstafetteServer, ListInstancesRequest, ListNamespacesRequest, NamespaceInfo,
+    RegisterHubRequest, RegisterInstanceRequest, RegisterResponse, RegisterServerRequest,
+};
+use crate::proto::gitstafette_info::InstanceType;
+
+// name the Discovery Server reports its health under
+const DISCOVERY_SERVICE: &str = "gitstafette_discovery.Discovery";
+// an unhealthy server must not hold up the failover to the next one
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+// identifies this client in list requests
+const CLIENT_ID: &str = "gsf-discovery-client";
+
+/// Typed client for one or more Discovery Servers.
+///
+/// Calls go to one server, over a connection per server that is shared by all calls. When a call fails because
+/// the server is unavailable, it is retried on the next server. A server a call failed on is checked with
+/// the gRPC health service before it is used again. With `register_all`,
+/// registrations go to every server instead. Every request carries the trace context and the token.
+#[derive(Debug, Clone)]
+pub struct DiscoveryClient {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    config: ClientConfig,
+    endpoints: Vec<Endpoint>,
+    /// one channel per endpoint, created on first use and shared by all calls
+    channels: Vec<OnceLock<Channel>>,
+    /// endpoints a call failed on, they are health checked before they are used again
+    failed: Vec<AtomicBool>,
+    /// the endpoint that was healthy last, it is used first
+    preferred: AtomicUsize,
+}
+
+impl DiscoveryClient {
+    /// Validates the configuration, connections are only made by the calls
+    pub fn new(config: ClientConfig) -> Result<Self, ClientError> {
+        if config.endpoints.is_empty() {
+            return Err(ClientError::Config("no Discovery Server endpoint".to_string()));
+        }
+        if let Some(token) = &config.token {
+            MetadataValue::try_from(format!("Bearer {}", token)).map_err(|_| ClientError::Config("invalid token".to_string()))?;
+        }
+        let mut endpoints = Vec::new();
+        for address in &config.endpoints {
+            endpoints.push(endpoint(address, &config)?);
+        }
+        Ok(DiscoveryClient {
+            inner: Arc::new(Inner {
+                config,
+                channels: endpoints.iter().map(|_| OnceLock::new()).collect(),
+                failed: endpoints.iter().map(|_| AtomicBool::new(false)).collect(),
+                endpoints,
+                preferred: AtomicUsize::new(0),
+            }),
+        })
+    }
+
+    pub fn config(&self) -> &ClientConfig {
+        &self.inner.config
+    }
+
+    pub async fn register_hub(&self, hub: GitstafetteHub, cx: &Context) -> Result<(), ClientError> {
+        let message = RegisterHubRequest {
+            hub: Some(hub),
+            namespace: self.namespace(),
+            expected_version: 0,
+        };
+        let register = |mut client: GrpcClient<Channel>| {
+            let request = self.request(message.clone(), cx);
+            async move { client.register_hub(request).await.map(|response| response.into_inner().response) }
+        };
+        if self.inner.config.register_all {
+            any_accepted(self.on_all(register).await)
+        } else {
+            accepted(self.call(register).await?)
+        }
+    }
+
+    pub async fn register_server(&self, server: GitstafetteServer, cx: &Context) -> Result<(), ClientError> {
+        let message = RegisterServerRequest {
+            server: Some(server),
+            namespace: self.namespace(),
+            expected_version: 0,
+        };
+        let register = |mut client: GrpcClient<Channel>| {
+            let request = self.request(message.clone(), cx);
+            async move { client.register_server(request).await.map(|response| response.into_inner().response) }
+        };
+        if self.inner.config.register_all {
+            any_accepted(self.on_all(register).await)
+        } else {
+            accepted(self.call(register).await?)
+        }
+    }
+
+    /// Registers an instance of any type, hubs and servers are also available as such
+    pub async fn register_instance(&self, instance: GitstafetteInstance, cx: &Context) -> Result<(), ClientError> {
+        let message = RegisterInstanceRequest {
+            instance: Some(instance),
+            namespace: self.namespace(),
+            expected_version: 0,
+        };
+        let register = |mut client: GrpcClient<Channel>| {
+            let request = self.request(message.clone(), cx);
+            async move { client.register_instance(request).await.map(|response| response.into_inner().response) }
+        };
+        if self.inner.config.register_all {
+            any_accepted(self.on_all(register).await)
+        } else {
+            accepted(self.call(register).await?)
+        }
+    }
+
+    /// Replaces the registration of the hub only while it is at `expected_version`, the resource version it was read with,
+    /// and returns its new resource version. Fails with ABORTED when it changed since, or is no longer registered.
+    /// Goes to a single Discovery Server, as every server has its own resource versions.
+    pub async fn update_hub(&self, hub: GitstafetteHub, expected_version: u64, cx: &Context) -> Result<u64, ClientError> {
+        let message = RegisterHubRequest {
+            hub: Some(hub),
+            namespace: self.namespace(),
+            expected_version,
+        };
+        let response = self
+            .call(|mut client| {
+                let request = self.request(message.clone(), cx);
+                async move { client.register_hub(request).await.map(|response| response.into_inner().response) }
+            })
+            .await?;
+        resource_version(response)
+    }
+
+    /// Replaces the registration of the server only while it is at `expected_version`, see `update_hub`
+    pub async fn update_server(&self, server: GitstafetteServer, expected_version: u64, cx: &Context) -> Result<u64, ClientError> {
+        let message = RegisterServerRequest {
+            server: Some(server),
+            namespace: self.namespace(),
+            expected_version,
+        };
+        let response = self
+            .call(|mut client| {
+                let request = self.request(message.clone(), cx);
+                async move { client.register_server(request).await.map(|response| response.into_inner().response) }
+            })
+            .await?;
+        resource_version(response)
+    }
+
+    /// Replaces the registration of the instance only while it is at `expected_version`, see `update_hub`
+    pub async fn update_instance(&self, instance: GitstafetteInstance, expected_version: u64, cx: &Context) -> Result<u64, ClientError> {
+        let message = RegisterInstanceRequest {
+            instance: Some(instance),
+            namespace: self.namespace(),
+            expected_version,
+        };
+        let response = self
+            .call(|mut client| {
+                let request = self.request(message.clone(), cx);
+                async move { client.register_instance(request).await.map(|response| response.into_inner().response) }
+            })
+            .await?;
+        resource_version(response)
+    }
+
+    /// Deregisters from every Discovery Server, as the hub may have been registered with another one after a failover.
+    /// Fails with NOT_FOUND when none of them knows the hub.
+    pub async fn deregister_hub(&self, id: &str, cx: &Context) -> Result<(), ClientError> {
+        let message = DeregisterHubRequest {
+            id: id.to_string(),
+            namespace: self.namespace(),
+        };
+        let results = self
+            .on_all(|mut client| {
+                let request = self.request(message.clone(), cx);
+                async move { client.deregister_hub(request).await.map(|_| ()) }
+            })
+            .await;
+        any_deregistered(results)
+    }
+
+    /// Deregisters from every Discovery Server, as the server may have been registered with another one after a failover.
+    /// Fails with NOT_FOUND when none of them knows the server.
+    pub async fn deregister_server(&self, id: &str, cx: &Context) -> Result<(), ClientError> {
+        let message = DeregisterServerRequest {
+            id: id.to_string(),
+            namespace: self.namespace(),
+        };
+        let results = self
+            .on_all(|mut client| {
+                let request = self.request(message.clone(), cx);
+                async move { client.deregister_server(request).await.map(|_| ()) }
+            })
+            .await;
+        any_deregistered(results)
+    }
+
+    /// Deregisters from every Discovery Server, as the instance may have been registered with another one after a failover.
+    /// Fails with NOT_FOUND when none of them knows the instance.
+    pub async fn deregister_instance(&self, instance_type: InstanceType, id: &str, cx: &Context) -> Result<(), ClientError> {
+        let message = DeregisterInstanceRequest {
+            id: id.to_string(),
+            instance_type: instance_type.into(),
+            namespace: self.namespace(),
+        };
+        let results = self
+            .on_all(|mut client| {
+                let request = self.request(message.clone(), cx);
+                async move { client.deregister_instance(request).await.map(|_| ()) }
+            })
+            .await;
+        any_deregistered(results)
+    }
+
+    /// The registered hubs, without those that failed their health probes unless `include_unhealthy` is set
+    pub async fn get_hubs(&self, include_unhealthy: bool, cx: &Context) -> Result<Vec<GitstafetteHub>, ClientError> {
+        self.get_hubs_matching("", include_unhealthy, cx).await
+    }
+
+    /// The registered hubs with labels that match the selector, e.g. `env=prod,zone in (a,b),!canary`.
+    /// An invalid selector fails with INVALID_ARGUMENT.
+    pub async fn get_hubs_matching(&self, label_selector: &str, include_unhealthy: bool, cx: &Context) -> Result<Vec<GitstafetteHub>, ClientError> {
+        let response = self.get_hubs_blocking(label_selector, include_unhealthy, 0, Duration::ZERO, cx).await?;
+        Ok(response.hubs)
+    }
+
+    /// A blocking query: with the `index` of a previous response, the Discovery Server holds the request until the hubs
+    /// changed since, or until `wait_timeout` expires (its default when zero), and returns them with the new index.
+    /// Without an index it returns right away. The `timeout` of the configuration must be longer than `wait_timeout`.
+    pub async fn get_hubs_blocking(
+        &self,
+        label_selector: &str,
+        include_unhealthy: bool,
+        index: u64,
+        wait_timeout: Duration,
+        cx: &Context,
+    ) -> Result<GetHubsResponse, ClientError> {
+        let message = GetHubsRequest {
+            client_id: CLIENT_ID.to_string(),
+            name: CLIENT_ID.to_string(),
+            host: "".to_string(),
+            port: "0".to_string(),
+            include_unhealthy,
+            label_selector: label_selector.to_string(),
+            namespace: self.namespace(),
+            wait_index: index,
+            wait_timeout_ms: u32::try_from(wait_timeout.as_millis()).unwrap_or(u32::MAX),
+        };
+        self.call(|mut client| {
+            let request = self.request(message.clone(), cx);
+            async move { client.get_hubs(request).await.map(|response| response.into_inner()) }
+        })
+        .await
+    }
+
+    /// The registered servers, without those that failed their health probes unless `include_unhealthy` is set
+    pub async fn get_servers(&self, include_unhealthy: bool, cx: &Context) -> Result<Vec<GitstafetteServer>, ClientError> {
+        self.get_servers_matching("", include_unhealthy, cx).await
+    }
+
+    /// The registered servers with labels that match the selector, e.g. `env=prod,zone in (a,b),!canary`.
+    /// An invalid selector fails with INVALID_ARGUMENT.
+    pub async fn get_servers_matching(&self, label_selector: &str, include_unhealthy: bool, cx: &Context) -> Result<Vec<GitstafetteServer>, ClientError> {
+        let response = self.get_servers_blocking(label_selector, include_unhealthy, 0, Duration::ZERO, cx).await?;
+        Ok(response.servers)
+    }
+
+    /// A blocking query: with the `index` of a previous response, the Discovery Server holds the request until the servers
+    /// changed since, or until `wait_timeout` expires (its default when zero), and returns them with the new index.
+    /// Without an index it returns right away. The `timeout` of the configuration must be longer than `wait_timeout`.
+    pub async fn get_servers_blocking(
+        &self,
+        label_selector: &str,
+        include_unhealthy: bool,
+        index: u64,
+        wait_timeout: Duration,
+        cx: &Context,
+    ) -> Result<GetServersResponse, ClientError> {
+        let message = GetServersRequest {
+            client_id: CLIENT_ID.to_string(),
+            name: CLIENT_ID.to_string(),
+            host: "".to_string(),
+            port: "0".to_string(),
+            include_unhealthy,
+            label_selector: label_selector.to_string(),
+            namespace: self.namespace(),
+            wait_index: index,
+            wait_timeout_ms: u32::try_from(wait_timeout.as_millis()).unwrap_or(u32::MAX),
+        };
+        self.call(|mut client| {
+            let request = self.request(message.clone(), cx);
+            async move { client.get_servers(request).await.map(|response| response.into_inner()) }
+        })
+        .await
+    }
+
+    /// The registered instances of the given types, or of every type when empty,
+    /// without those that failed their health probes unless `include_unhealthy` is set
+    pub async fn list_instances(&self, instance_types: &[InstanceType], include_unhealthy: bool, cx: &Context) -> Result<Vec<GitstafetteInstance>, ClientError> {
+        self.list_instances_matching(instance_types, "", include_unhealthy, cx).await
+    }
+
+    /// The registered instances of the given types with labels that match the selector.
+    /// An invalid selector fails with INVALID_ARGUMENT.
+    pub async fn list_instances_matching(
+        &self,
+        instance_types: &[InstanceType],
+        label_selector: &str,
+        include_unhealthy: bool,
+        cx: &Context,
+    ) -> Result<Vec<GitstafetteInstance>, ClientError> {
+        let message = ListInstancesRequest {
+            client_id: CLIENT_ID.to_string(),
+            instance_types: instance_types.iter().map(|instance_type| (*instance_type).into()).collect(),
+            include_unhealthy,
+            label_selector: label_selector.to_string(),
+            namespace: self.namespace(),
+        };
+        self.call(|mut client| {
+            let request = self.request(message.clone(), cx);
+            async move { client.list_instances(request).await.map(|response| response.into_inner().instances) }
+        })
+        .await
+    }
+
+    /// The Info of every registered instance, `timeout_ms` 0 uses the default of the Discovery Server
+    pub async fn get_fleet_info(&self, timeout_ms: u32, cx: &Context) -> Result<Vec<FleetInstanceInfo>, ClientError> {
+        let message = GetFleetInfoRequest {
+            client_id: CLIENT_ID.to_string(),
+            timeout_ms,
+            namespace: self.namespace(),
+        };
+        self.call(|mut client| {
+            let request = self.request(message.clone(), cx);
+            async move { client.get_fleet_info(request).await.map(|response| response.into_inner().instances) }
+        })
+        .await
+    }
+
+    /// The namespaces the caller may read, with the number of instances in each
+    pub async fn list_namespaces(&self, cx: &Context) -> Result<Vec<NamespaceInfo>, ClientError> {
+        let message = ListNamespacesRequest {
+            client_id: CLIENT_ID.to_string(),
+        };
+        self.call(|mut client| {
+            let request = self.request(message.clone(), cx);
+            async move { client.list_namespaces(request).await.map(|response| response.into_inner().namespaces) }
+        })
+        .await
+    }
+
+    /// The registrations that changed since the revision of the previous response, 0 for all changes since the server started.
+    /// When those are no longer available, the response has `reset` set, and the caller lists all instances again
+    /// before asking for the changes since the returned revision. Health and relay status transitions are updates.
+    pub async fn get_changes(&self, since_revision: u64, cx: &Context) -> Result<GetChangesResponse, ClientError> {
+        let message = GetChangesRequest {
+            client_id: CLIENT_ID.to_string(),
+            since_revision,
+            namespace: self.namespace(),
+        };
+        self.call(|mut client| {
+            let request = self.request(message.clone(), cx);
+            async move { client.get_changes(request).await.map(|response| response.into_inner()) }
+        })
+        .await
+    }
+
+    /// empty for the default namespace of the Discovery Server
+    fn namespace(&self) -> String {
+        self.inner.config.namespace.clone().unwrap_or_default()
+    }
+
+    fn request<T>(&self, message: T, cx: &Context) -> tonic::Request<T> {
+        let mut request = tonic::Request::new(message);
+        global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(cx, &mut MetadataMap(request.metadata_mut()))
+        });
+        if let Some(token) = &self.inner.config.token {
+            // validated in new
+            if let Ok(value) = MetadataValue::try_from(format!("Bearer {}", token)) {
+                request.metadata_mut().insert("authorization", value);
+            }
+        }
+        request
+    }
+
+    /// Calls a server, retrying on the next one while the servers are unavailable
+    async fn call<R, F, Fut>(&self, call: F) -> Result<R, ClientError>
+    where
+        F: Fn(GrpcClient<Channel>) -> Fut,
+        Fut: Future<Output = Result<R, tonic::Status>>,
+    {
+        let mut delay = self.inner.config.retry_backoff;
+        let mut attempt = 0;
+        loop {
+            let error = match self.connect().await {
+                Ok((index, client)) => match call(client).await {
+                    Ok(response) => return Ok(response),
+                    Err(status) if retryable(&status) => {
+                        self.failed(index);
+                        ClientError::from(status)
+                    }
+                    Err(status) => return Err(ClientError::from(status)),
+                },
+                Err(error) => error,
+            };
+            if attempt >= self.inner.config.retries {
+                return Err(error);
+            }
+            attempt += 1;
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    /// Calls every server once, in order
+    async fn on_all<R, F, Fut>(&self, call: F) -> Vec<Result<R, tonic::Status>>
+    where
+        F: Fn(GrpcClient<Channel>) -> Fut,
+        Fut: Future<Output = Result<R, tonic::Status>>,
+    {
+        let mut results = Vec::new();
+        for index in 0..self.inner.endpoints.len() {
+            results.push(call(GrpcClient::new(self.channel(index))).await);
+        }
+        results
+    }
+
+    /// The shared channel of the endpoint, it connects on the first call and reconnects after errors
+    fn channel(&self, index: usize) -> Channel {
+        self.inner.channels[index].get_or_init(|| self.inner.endpoints[index].connect_lazy()).clone()
+    }
+
+    /// A client for the preferred server, or for the next healthy one when a call to it failed.
+    /// Only servers a call failed on are health checked, a single server never is.
+    async fn connect(&self) -> Result<(usize, GrpcClient<Channel>), ClientError> {
+        let endpoints = &self.inner.endpoints;
+        if endpoints.len() == 1 {
+            return Ok((0, GrpcClient::new(self.channel(0))));
+        }
+
+        let preferred = self.inner.preferred.load(Ordering::Relaxed);
+        let mut errors = Vec::new();
+        for offset in 0..endpoints.len() {
+            let index = (preferred + offset) % endpoints.len();
+            let channel = self.channel(index);
+            if self.inner.failed[index].load(Ordering::Relaxed) {
+                if let Err(error) = check_health(channel.clone()).await {
+                    errors.push(format!("{}: {}", endpoints[index].uri(), error));
+                    continue;
+                }
+                self.inner.failed[index].store(false, Ordering::Relaxed);
+            }
+            if index != preferred {
+                tracing::warn!("Failing over to Discovery Server {}", endpoints[index].uri());
+                self.inner.preferred.store(index, Ordering::Relaxed);
+            }
+            return Ok((index, GrpcClient::new(channel)));
+        }
+        Err(ClientError::Unavailable(errors.join(", ")))
+    }
+
+    /// Marks the server as failed, so it is health checked before its next call, and the next call starts with the one after it
+    fn failed(&self, index: usize) {
+        self.inner.failed[index].store(true, Ordering::Relaxed);
+        let next = (index + 1) % self.inner.endpoints.len();
+        if next != index && self.inner.preferred.compare_exchange(index, next, Ordering::Relaxed, Ordering::Relaxed).is_ok() {
+            tracing::warn!("Failing over to Discovery Server {}", self.inner.endpoints[next].uri());
+        }
+    }
+}
+
+fn endpoint(address: &str, config: &ClientConfig) -> Result<Endpoint, ClientError> {
+    let invalid = |error: tonic::transport::Error| ClientError::Config(format!("invalid endpoint {}: {}", address, error));
+    let mut endpoint = Endpoint::from_shared(address.to_string())
+        .map_err(invalid)?
+        .connect_timeout(config.connect_timeout);
+    if let Some(timeout) = config.timeout {
+        endpoint = endpoint.timeout(timeout);
+    }
+    if endpoint.uri().scheme_str() == Some("https") {
+        endpoint = endpoint.tls_config(tls_config(&config.tls)).map_err(invalid)?;
+    }
+    Ok(endpoint)
+}
+
+pub(crate) fn tls_config(tls: &TlsConfig) -> ClientTlsConfig {
+    let mut tls_config = ClientTlsConfig::new();
+    if let Some(ca_certificate) = &tls.ca_certificate {
+        tls_config = tls_config.ca_certificate(Certificate::from_pem(ca_certificate));
+    }
+    if let Some(domain) = &tls.domain {
+        tls_config = tls_config.domain_name(domain);
+    }
+    if let Some((certificate, key)) = &tls.identity {
+        tls_config = tls_config.identity(Identity::from_pem(certificate, key));
+    }
+    tls_config
+}
+
+/// failures another server, or a later attempt, may not have.
+/// A call that timed out may have been applied, so it is not retried, as registrations are not idempotent.
+fn retryable(status: &tonic::Status) -> bool {
+    status.code() == tonic::Code::Unavailable
+}
+
+async fn check_health(channel: Channel) -> Result<(), String> {
+    let check = async {
+        let mut health_client = HealthClient::new(channel);
+        let request = HealthCheckRequest { service: DISCOVERY_SERVICE.to_string() };
+        let response = health_client.check(request).await.map_err(|status| status.message().to_string())?;
+        if response.get_ref().status == ServingStatus::Serving as i32 {
+            Ok(())
+        } else {
+            Err(format!("health check status: {:?}", ServingStatus::try_from(response.get_ref().status).unwrap_or(ServingStatus::Unknown)))
+        }
+    };
+    tokio::time::timeout(HEALTH_CHECK_TIMEOUT, check)
+        .await
+        .unwrap_or_else(|_| Err("health check timed out".to_string()))
+}
+
+fn accepted(response: Option<RegisterResponse>) -> Result<(), ClientError> {
+    match response {
+        Some(response) if !response.success => Err(ClientError::Rejected {
+            error_code: response.error_code,
+            message: response.error,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// the resource version of an accepted registration
+fn resource_version(response: Option<RegisterResponse>) -> Result<u64, ClientError> {
+    let resource_version = response.as_ref().map(|response| response.resource_version).unwrap_or_default();
+    accepted(response).map(|_| resource_version)
+}
+
+/// a registration with several servers succeeds when at least one of them accepted it
+fn any_accepted(results: Vec<Result<Option<RegisterResponse>, tonic::Status>>) -> Result<(), ClientError> {
+    let mut last_error = None;
+    for result in results {
+        match result.map_err(ClientError::from).and_then(accepted) {
+            Ok(()) => return Ok(()),
+            Err(error) => last_error = Some(error),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| ClientError::Unavailable("no endpoints".to_string())))
+}
+
+/// a deregistration from several servers succeeds when at least one of them knew the instance,
+/// and fails with NOT_FOUND only when none of them had another error
+fn any_deregistered(results: Vec<Result<(), tonic::Status>>) -> Result<(), ClientError> {
+    let mut error: Option<tonic::Status> = None;
+    for result in results {
+        match result {
+            Ok(()) => return Ok(()),
+            Err(status) if status.code() == tonic::Code::NotFound => {
+                error.get_or_insert(status);
+            }
+            Err(status) => error = Some(status),
+        }
+    }
+    Err(error.map(ClientError::from).unwrap_or_else(|| ClientError::Unavailable("no endpoints".to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU32;
+
+    use tonic::transport::server::TcpIncoming;
+    use tonic::transport::Server;
+    use tonic::{Code, Status};
+    use tonic_health::server::HealthReporter;
+
+    use super::*;
+
+    // nothing listens on it, connections are refused right away
+    const UNREACHABLE: &str = "http://127.0.0.1:1";
+
+    fn registered(resource_version: u64) -> Option<RegisterResponse> {
+        Some(RegisterResponse {
+            success: true,
+            resource_version,
+            ..RegisterResponse::default()
+        })
+    }
+
+    fn rejected(error_code: &str) -> Option<RegisterResponse> {
+        Some(RegisterResponse {
+            success: false,
+            error_code: error_code.to_string(),
+            error: "rejected".to_string(),
+            ..RegisterResponse::default()
+        })
+    }
+
+    fn client(endpoints: &[&str], retries: u32) -> DiscoveryClient {
+        let mut config = ClientConfig::new(endpoints.iter().map(|endpoint| endpoint.to_string()).collect());
+        config.retries = retries;
+        config.retry_backoff = Duration::from_millis(1);
+        DiscoveryClient::new(config).unwrap()
+    }
+
+    /// A server with only the health service, reporting the Discovery service as serving
+    async fn healthy_server() -> (String, HealthReporter) {
+        let (mut reporter, service) = tonic_health::server::health_reporter();
+        reporter.set_service_status(DISCOVERY_SERVICE, tonic_health::ServingStatus::Serving).await;
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let incoming = TcpIncoming::from_listener(listener, true, None).unwrap();
+        tokio::spawn(Server::builder().add_service(service).serve_with_incoming(incoming));
+        (address, reporter)
+    }
+
+    async fn connected_to(client: &DiscoveryClient) -> usize {
+        client.connect().await.unwrap().0
+    }
+
+    fn is_failed(client: &DiscoveryClient, index: usize) -> bool {
+        client.inner.failed[index].load(Ordering::Relaxed)
+    }
+
+    #[test]
+    fn accepts_registrations() {
+        assert!(accepted(registered(3)).is_ok());
+        // servers without a response message accept every registration
+        assert!(accepted(None).is_ok());
+        let error = accepted(rejected("INVALID_INSTANCE")).unwrap_err();
+        assert!(matches!(&error, ClientError::Rejected { error_code, .. } if error_code == "INVALID_INSTANCE"));
+        assert_eq!(error.code(), Code::FailedPrecondition);
+    }
+
+    #[test]
+    fn returns_the_resource_version_of_accepted_registrations() {
+        assert_eq!(resource_version(registered(7)).unwrap(), 7);
+        assert_eq!(resource_version(None).unwrap(), 0);
+        assert!(matches!(resource_version(rejected("CONFLICT")), Err(ClientError::Rejected { .. })));
+    }
+
+    #[test]
+    fn accepts_a_registration_when_any_server_does() {
+        assert!(any_accepted(vec![Err(Status::unavailable("down")), Ok(rejected("INVALID_INSTANCE")), Ok(registered(1))]).is_ok());
+
+        let error = any_accepted(vec![Err(Status::unavailable("down")), Ok(rejected("INVALID_INSTANCE"))]).unwrap_err();
+        assert!(matches!(error, ClientError::Rejected { .. }), "the last error is returned: {}", error);
+        let error = any_accepted(vec![Ok(rejected("INVALID_INSTANCE")), Err(Status::unavailable("down"))]).unwrap_err();
+        assert_eq!(error.code(), Code::Unavailable);
+        assert_eq!(any_accepted(vec![]).unwrap_err().code(), Code::Unavailable);
+    }
+
+    #[test]
+    fn deregisters_when_any_server_knew_the_instance() {
+        assert!(any_deregistered(vec![Err(Status::not_found("unknown")), Err(Status::unavailable("down")), Ok(())]).is_ok());
+        assert_eq!(any_deregistered(vec![Err(Status::not_found("unknown")), Err(Status::not_found("unknown"))]).unwrap_err().code(), Code::NotFound);
+        // a server that could not answer may have known the instance
+        assert_eq!(any_deregistered(vec![Err(Status::not_found("unknown")), Err(Status::unavailable("down"))]).unwrap_err().code(), Code::Unavailable);
+        assert_eq!(any_deregistered(vec![Err(Status::unavailable("down")), Err(Status::not_found("unknown"))]).unwrap_err().code(), Code::Unavailable);
+        assert_eq!(any_deregistered(vec![]).unwrap_err().code(), Code::Unavailable);
+    }
+
+    #[test]
+    fn retries_only_unavailable_servers() {
+        assert!(retryable(&Status::unavailable("down")));
+        // the call may have been applied
+        assert!(!retryable(&Status::deadline_exceeded("timed out")));
+        assert!(!retryable(&Status::not_found("unknown")));
+        assert!(!retryable(&Status::aborted("conflict")));
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_the_next_server() {
+        let client = client(&[UNREACHABLE, "http://127.0.0.1:2"], 0);
+        // servers are only health checked after a call failed on them
+        assert_eq!(connected_to(&client).await, 0);
+
+        client.failed(0);
+        assert_eq!(connected_to(&client).await, 1);
+        client.failed(1);
+        let error = client.connect().await.unwrap_err();
+        assert!(matches!(&error, ClientError::Unavailable(message) if message.contains("127.0.0.1:1") && message.contains("127.0.0.1:2")), "{}", error);
+        assert!(is_failed(&client, 0) && is_failed(&client, 1));
+    }
+
+    #[tokio::test]
+    async fn uses_a_failed_server_again_once_it_is_healthy() {
+        let (address, mut reporter) = healthy_server().await;
+        let client = client(&[&address, UNREACHABLE], 0);
+        client.failed(0);
+        assert_eq!(connected_to(&client).await, 1);
+
+        reporter.set_service_status(DISCOVERY_SERVICE, tonic_health::ServingStatus::NotServing).await;
+        client.failed(1);
+        assert!(client.connect().await.is_err());
+
+        reporter.set_service_status(DISCOVERY_SERVICE, tonic_health::ServingStatus::Serving).await;
+        assert_eq!(connected_to(&client).await, 0);
+        assert!(!is_failed(&client, 0));
+        // it stays preferred
+        assert_eq!(connected_to(&client).await, 0);
+    }
+
+    #[tokio::test]
+    async fn never_health_checks_a_single_server() {
+        let client = client(&[UNREACHABLE], 0);
+        client.failed(0);
+        assert_eq!(connected_to(&client).await, 0);
+    }
+
+    #[tokio::test]
+    async fn retries_calls_on_the_next_server() {
+        let client = client(&[UNREACHABLE, "http://127.0.0.1:2"], 1);
+        let calls = AtomicU32::new(0);
+        let error = client
+            .call(|_| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                async { Err::<(), _>(Status::unavailable("down")) }
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), Code::Unavailable);
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        assert!(is_failed(&client, 0) && is_failed(&client, 1));
+    }
+
+    #[tokio::test]
+    async fn does_not_fail_over_on_other_errors() {
+        let client = client(&[UNREACHABLE, "http://127.0.0.1:2"], 1);
+        let calls = AtomicU32::new(0);
+        let error = client
+            .call(|_| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                async { Err::<(), _>(Status::deadline_exceeded("timed out")) }
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), Code::DeadlineExceeded);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert!(!is_failed(&client, 0));
+        assert_eq!(connected_to(&client).await, 0);
+    }
+}
@@ -0,0 +1,42 @@
+#[derive(Debug)]
+pub enum ClientError {
+    /// an endpoint or the TLS settings are invalid
+    Config(String),
+    /// none of the Discovery Servers is healthy
+    Unavailable(String),
+    /// the Discovery Server returned an error
+    Rpc(Box<tonic::Status>),
+    /// the Discovery Server did not accept the registration
+    Rejected { error_code: String, message: String },
+}
+
+impl ClientError {
+    /// the gRPC status code that describes the error best
+    pub fn code(&self) -> tonic::Code {
+        match self {
+            ClientError::Config(_) => tonic::Code::InvalidArgument,
+            ClientError::Unavailable(_) => tonic::Code::Unavailable,
+            ClientError::Rpc(status) => status.code(),
+            ClientError::Rejected { .. } => tonic::Code::FailedPrecondition,
+        }
+    }
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Config(message) => write!(f, "invalid configuration: {}", message),
+            ClientError::Unavailable(message) => write!(f, "no healthy Discovery Server: {}", message),
+            ClientError::Rpc(status) => write!(f, "{:?} {}", status.code(), status.message()),
+            ClientError::Rejected { error_code, message } => write!(f, "registration rejected: {} {}", error_code, message),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<tonic::Status> for ClientError {
+    fn from(status: tonic::Status) -> Self {
+        ClientError::Rpc(Box::new(status))
+    }
+}
\ No newline at end of file
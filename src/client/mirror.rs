@@ -0,0 +1,269 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use opentelemetry::Context;
+use prost::Message;
+
+use crate::client::discovery::DiscoveryClient;
+use crate::client::error::ClientError;
+use crate::proto::gitstafette_discovery::{GitstafetteHub, GitstafetteServer};
+
+#[derive(Debug, Clone)]
+pub struct MirrorConfig {
+    /// age after which the mirror is refreshed
+    pub refresh_interval: Duration,
+    /// file the last good snapshot is written to, and read from on start
+    pub snapshot_file: Option<PathBuf>,
+    /// also mirror hubs and servers that failed their health probes
+    pub include_unhealthy: bool,
+    /// only mirror hubs and servers with labels that match this selector, e.g. `env=prod`
+    pub label_selector: Option<String>,
+}
+
+impl Default for MirrorConfig {
+    fn default() -> Self {
+        MirrorConfig {
+            refresh_interval: Duration::from_secs(10),
+            snapshot_file: None,
+            include_unhealthy: false,
+            label_selector: None,
+        }
+    }
+}
+
+/// Where the data of a snapshot came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotSource {
+    DiscoveryServer,
+    /// the snapshot file, written before the client restarted
+    File,
+}
+
+/// The hubs and servers as they were at `fetched_at`
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub hubs: Vec<GitstafetteHub>,
+    pub servers: Vec<GitstafetteServer>,
+    pub fetched_at: SystemTime,
+    pub source: SnapshotSource,
+}
+
+impl Snapshot {
+    /// time since the snapshot was fetched from a Discovery Server
+    pub fn age(&self) -> Duration {
+        SystemTime::now().duration_since(self.fetched_at).unwrap_or_default()
+    }
+}
+
+/// A local copy of the registry, for callers that must keep working while the Discovery Servers are unavailable.
+///
+/// `get` serves the snapshot and refreshes it in the background once it is older than the refresh interval
+/// (stale-while-revalidate). When refreshing fails, the last good snapshot is kept, so callers decide
+/// with `Snapshot::age` how stale is acceptable. With a snapshot file, the last good snapshot survives restarts.
+#[derive(Debug, Clone)]
+pub struct RegistryMirror {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    client: DiscoveryClient,
+    config: MirrorConfig,
+    snapshot: RwLock<Option<Arc<Snapshot>>>,
+    last_error: RwLock<Option<String>>,
+    /// only one background refresh at a time
+    refreshing: AtomicBool,
+}
+
+impl RegistryMirror {
+    /// Starts with the snapshot file, if it exists, is valid and was fetched for the same namespace and selector,
+    /// without calling a Discovery Server
+    pub fn new(client: DiscoveryClient, config: MirrorConfig) -> Self {
+        let scope = SnapshotScope::new(&client, &config);
+        let snapshot = config.snapshot_file.as_deref().and_then(|path| match read_snapshot(path, &scope) {
+            Ok(snapshot) => snapshot.map(Arc::new),
+            Err(error) => {
+                tracing::warn!("Ignoring registry snapshot {}: {}", path.display(), error);
+                None
+            }
+        });
+        RegistryMirror {
+            inner: Arc::new(Inner {
+                client,
+                config,
+                snapshot: RwLock::new(snapshot),
+                last_error: RwLock::new(None),
+                refreshing: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// The current snapshot, however old it is
+    pub fn current(&self) -> Option<Arc<Snapshot>> {
+        self.inner.snapshot.read().unwrap().clone()
+    }
+
+    /// Why the last refresh failed, cleared by a successful one
+    pub fn last_error(&self) -> Option<String> {
+        self.inner.last_error.read().unwrap().clone()
+    }
+
+    /// The current snapshot, refreshed in the background when it is older than the refresh interval.
+    /// Only waits for a Discovery Server when there is no snapshot yet.
+    pub async fn get(&self, cx: &Context) -> Result<Arc<Snapshot>, ClientError> {
+        match self.current() {
+            Some(snapshot) => {
+                if snapshot.age() >= self.inner.config.refresh_interval {
+                    self.refresh_in_background(cx);
+                }
+                Ok(snapshot)
+            }
+            None => self.refresh(cx).await,
+        }
+    }
+
+    /// Fetches the hubs and servers from a Discovery Server, and persists them to the snapshot file.
+    /// On an error the current snapshot is kept.
+    pub async fn refresh(&self, cx: &Context) -> Result<Arc<Snapshot>, ClientError> {
+        match self.fetch(cx).await {
+            Ok(snapshot) => {
+                let snapshot = Arc::new(snapshot);
+                if let Some(path) = &self.inner.config.snapshot_file {
+                    let scope = SnapshotScope::new(&self.inner.client, &self.inner.config);
+                    if let Err(error) = write_snapshot(path, &snapshot, &scope) {
+                        tracing::warn!("Failed to write registry snapshot {}: {}", path.display(), error);
+                    }
+                }
+                *self.inner.snapshot.write().unwrap() = Some(snapshot.clone());
+                *self.inner.last_error.write().unwrap() = None;
+                Ok(snapshot)
+            }
+            Err(error) => {
+                *self.inner.last_error.write().unwrap() = Some(error.to_string());
+                Err(error)
+            }
+        }
+    }
+
+    /// Refreshes every refresh interval, so `get` rarely serves a stale snapshot. Never returns.
+    pub async fn run(&self, cx: &Context) {
+        loop {
+            if let Err(error) = self.refresh(cx).await {
+                tracing::warn!("Failed to refresh the registry mirror: {}", error);
+            }
+            tokio::time::sleep(self.inner.config.refresh_interval).await;
+        }
+    }
+
+    fn refresh_in_background(&self, cx: &Context) {
+        if self.inner.refreshing.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let mirror = self.clone();
+        let cx = cx.clone();
+        let refreshing = RefreshingGuard(self.inner.clone());
+        tokio::spawn(async move {
+            let _refreshing = refreshing;
+            // the error is kept for last_error, the stale snapshot is still served
+            let _ = mirror.refresh(&cx).await;
+        });
+    }
+
+    async fn fetch(&self, cx: &Context) -> Result<Snapshot, ClientError> {
+        let include_unhealthy = self.inner.config.include_unhealthy;
+        let label_selector = self.inner.config.label_selector.as_deref().unwrap_or_default();
+        let hubs = self.inner.client.get_hubs_matching(label_selector, include_unhealthy, cx).await?;
+        let servers = self.inner.client.get_servers_matching(label_selector, include_unhealthy, cx).await?;
+        Ok(Snapshot {
+            hubs,
+            servers,
+            fetched_at: SystemTime::now(),
+            source: SnapshotSource::DiscoveryServer,
+        })
+    }
+}
+
+/// Allows the next background refresh once the current one ends, also when it panics or is aborted
+struct RefreshingGuard(Arc<Inner>);
+
+impl Drop for RefreshingGuard {
+    fn drop(&mut self) {
+        self.0.refreshing.store(false, Ordering::Release);
+    }
+}
+
+/// What a snapshot was fetched for, a snapshot file of another scope is not served
+#[derive(Debug, Clone, PartialEq)]
+struct SnapshotScope {
+    namespace: String,
+    label_selector: String,
+    include_unhealthy: bool,
+}
+
+impl SnapshotScope {
+    fn new(client: &DiscoveryClient, config: &MirrorConfig) -> Self {
+        SnapshotScope {
+            namespace: client.config().namespace.clone().unwrap_or_default(),
+            label_selector: config.label_selector.clone().unwrap_or_default(),
+            include_unhealthy: config.include_unhealthy,
+        }
+    }
+}
+
+/// The snapshot file, protobuf encoded like the responses it is made of
+#[derive(Clone, PartialEq, Message)]
+struct SnapshotFile {
+    #[prost(message, repeated, tag = "1")]
+    hubs: Vec<GitstafetteHub>,
+    #[prost(message, repeated, tag = "2")]
+    servers: Vec<GitstafetteServer>,
+    #[prost(uint64, tag = "3")]
+    fetched_at_ms: u64,
+    #[prost(string, tag = "4")]
+    namespace: String,
+    #[prost(string, tag = "5")]
+    label_selector: String,
+    #[prost(bool, tag = "6")]
+    include_unhealthy: bool,
+}
+
+fn read_snapshot(path: &Path, scope: &SnapshotScope) -> Result<Option<Snapshot>, String> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error.to_string()),
+    };
+    let file = SnapshotFile::decode(bytes.as_slice()).map_err(|error| error.to_string())?;
+    let file_scope = SnapshotScope {
+        namespace: file.namespace,
+        label_selector: file.label_selector,
+        include_unhealthy: file.include_unhealthy,
+    };
+    if file_scope != *scope {
+        return Err("fetched for another namespace, label selector or include_unhealthy".to_string());
+    }
+    Ok(Some(Snapshot {
+        hubs: file.hubs,
+        servers: file.servers,
+        fetched_at: UNIX_EPOCH + Duration::from_millis(file.fetched_at_ms),
+        source: SnapshotSource::File,
+    }))
+}
+
+/// Writes next to the file and renames it over the file, so a crash never leaves a partial snapshot
+fn write_snapshot(path: &Path, snapshot: &Snapshot, scope: &SnapshotScope) -> std::io::Result<()> {
+    let file = SnapshotFile {
+        hubs: snapshot.hubs.clone(),
+        servers: snapshot.servers.clone(),
+        fetched_at_ms: snapshot.fetched_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+        namespace: scope.namespace.clone(),
+        label_selector: scope.label_selector.clone(),
+        include_unhealthy: scope.include_unhealthy,
+    };
+    let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let temporary = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+    std::fs::write(&temporary, file.encode_to_vec())?;
+    std::fs::rename(&temporary, path)
+}
\ No newline at end of file
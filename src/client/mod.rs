@@ -0,0 +1,6 @@
+pub mod balance;
+pub mod config;
+pub mod discovery;
+pub mod error;
+pub mod mirror;
+pub mod propagation;
\ No newline at end of file
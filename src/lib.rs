@@ -0,0 +1,24 @@
+//! Client library for the Gitstafette Discovery Server.
+//!
+//! [`DiscoveryClient`] registers hubs and servers and lists them, failing over between Discovery Servers.
+//!
+//! ```no_run
+//! use gsf_discovery::{ClientConfig, DiscoveryClient};
+//!
+//! # async fn example() -> Result<(), gsf_discovery::ClientError> {
+//! let config = ClientConfig::new(vec!["http://localhost:50051".to_string()]);
+//! let client = DiscoveryClient::new(config)?;
+//! let hubs = client.get_hubs(false, &opentelemetry::Context::current()).await?;
+//! println!("{} hubs", hubs.len());
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod client;
+pub mod proto;
+
+pub use client::balance::{balance_channel, BalanceConfig, DiscoveredEndpoints, InstanceFilter, InstanceKind};
+pub use client::config::{ClientConfig, TlsConfig};
+pub use client::discovery::DiscoveryClient;
+pub use client::error::ClientError;
+pub use client::mirror::{MirrorConfig, RegistryMirror, Snapshot};
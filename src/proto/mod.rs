@@ -0,0 +1,6 @@
+// https://timvw.be/2022/04/28/notes-on-using-grpc-with-rust-and-tonic/
+#[allow(clippy::derive_partial_eq_without_eq)] // tonic don't derive Eq for generated types. We shouldn't manually change it.
+pub mod gitstafette_discovery;
+
+#[allow(clippy::derive_partial_eq_without_eq)] // tonic don't derive Eq for generated types. We shouldn't manually change it.
+pub mod gitstafette_info;
\ No newline at end of file